@@ -0,0 +1,36 @@
+//! Writes the known answer test files for the enabled parameter set, the 
+//! same ones PQCgenKAT_sign.c produces, into tests/KAT or the given folder.
+//! 
+//! Usage: 
+//! ```shell
+//! cargo run --release --example genkat --features "sha2 s256 simple KAT"
+//! ```
+
+use std::path::PathBuf;
+
+use pqc_sphincsplus::{CRYPTO_SEEDBYTES, HASH, MODE, THASH};
+
+#[path = "../tests/genkat/mod.rs"]
+mod genkat;
+use genkat::*;
+
+fn main() {
+  let dir = match std::env::args().nth(1) {
+    Some(dir) => PathBuf::from(dir),
+    None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("KAT")
+  };
+  std::fs::create_dir_all(&dir).expect("Unable to create KAT folder");
+  let files = generate(KAT_COUNT);
+  for (name, contents) in [
+    (
+      format!("PQCsignKAT_sphincs-{}-{}-{}.rsp", HASH, MODE, THASH), 
+      &files.rsp
+    ),
+    (format!("SeedBufferKeygen_{}", CRYPTO_SEEDBYTES), &files.seed_buffer_keygen),
+    (format!("SeedBufferSign_{}", CRYPTO_SEEDBYTES / 3), &files.seed_buffer_sign)
+  ] {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).expect("Unable to write KAT file");
+    println!("{}", path.display());
+  }
+}
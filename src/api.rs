@@ -1,32 +1,524 @@
-use crate::params::{CRYPTO_SECRETKEYBYTES, CRYPTO_PUBLICKEYBYTES, CRYPTO_BYTES};
+use core::fmt;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek};
+#[cfg(feature = "prehash")]
+use crate::prehash::*;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use crate::params::{
+  CRYPTO_SECRETKEYBYTES, CRYPTO_PUBLICKEYBYTES, CRYPTO_BYTES, CRYPTO_SEEDBYTES, SPX_N,
+  Selected
+};
+use crate::context::Variant;
+use crate::randombytes::*;
 use crate::sign::*;
 
-#[derive(Copy, Clone)]
+/// A public key, formatted as [PUB_SEED || root]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+  bytes: [u8; CRYPTO_PUBLICKEYBYTES]
+}
+
+impl PublicKey {
+  /// Creates a public key from exactly CRYPTO_PUBLICKEYBYTES bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    let bytes = bytes.try_into().map_err(|_| SigError::PublicKeyLength)?;
+    Ok(Self { bytes })
+  }
+
+  /// Returns a copy of the encoded public key
+  pub fn to_bytes(&self) -> [u8; CRYPTO_PUBLICKEYBYTES]
+  {
+    self.bytes
+  }
+
+  fn open<'a>(&self, sm: &'a [u8]) -> Result<&'a [u8], SigError>
+  {
+    if sm.len() < CRYPTO_BYTES {
+      return Err(SigError::SignatureLength);
+    }
+    let (sig, msg) = sm.split_at(CRYPTO_BYTES);
+    crypto_sign_verify(sig, msg, &self.bytes)?;
+    Ok(msg)
+  }
+
+  #[cfg(feature = "std")]
+  fn verify_reader<R: Read>(&self, sig: &[u8], reader: &mut R) -> Result<(), SigError>
+  {
+    check_len(sig, CRYPTO_BYTES, SigError::SignatureLength)?;
+    spx_verify_msg::<Selected>(sig, &mut ReadMessage(reader), &self.bytes, Variant::Sphincs)
+  }
+
+  /// FIPS 205 verification of the message made of parts
+  #[cfg(any(feature = "slh-dsa", feature = "prehash"))]
+  fn verify_slh_dsa(&self, sig: &[u8], mut parts: &[&[u8]]) -> Result<(), SigError>
+  {
+    check_len(sig, CRYPTO_BYTES, SigError::SignatureLength)?;
+    spx_verify_msg::<Selected>(sig, &mut parts, &self.bytes, Variant::SlhDsa)
+  }
+
+  #[cfg(feature = "slh-dsa")]
+  fn verify_pure(&self, sig: &[u8], msg: &[u8], ctx: &[u8]) -> Result<(), SigError>
+  {
+    let header = slh_dsa_header(0, ctx)?;
+    self.verify_slh_dsa(sig, &[&header, ctx, msg])
+  }
+
+  #[cfg(feature = "prehash")]
+  fn verify_digest(
+    &self, sig: &[u8], digest: &[u8], ctx: &[u8], ph: PreHash
+  ) -> Result<(), SigError>
+  {
+    let header = prehash_header(ctx, ph, digest)?;
+    self.verify_slh_dsa(sig, &[&header, ctx, ph.oid(), digest])
+  }
+}
+
+impl AsRef<[u8]> for PublicKey {
+  fn as_ref(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+/// A secret key, formatted as [SK_SEED || SK_PRF || PUB_SEED || root]
+/// 
+/// This type is deliberately not `Copy`, its `Debug` output is redacted and
+/// equality is checked in constant time.
+#[derive(Clone)]
+pub struct SecretKey {
+  bytes: [u8; CRYPTO_SECRETKEYBYTES]
+}
+
+impl SecretKey {
+  /// Creates a secret key from exactly CRYPTO_SECRETKEYBYTES bytes
+  /// 
+  /// Only the length is checked, the stored root is trusted. Use 
+  /// [`SecretKey::from_bytes_checked`] to also check the key structure.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    let bytes = bytes.try_into().map_err(|_| SigError::SecretKeyLength)?;
+    Ok(Self { bytes })
+  }
+
+  /// Creates a secret key from exactly CRYPTO_SECRETKEYBYTES bytes and 
+  /// checks its stored root against SK_SEED and PUB_SEED, see 
+  /// [`SecretKey::validate`]
+  /// 
+  /// Costs a full subtree computation, like key generation.
+  pub fn from_bytes_checked(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    let sk = Self::from_bytes(bytes)?;
+    sk.validate()?;
+    Ok(sk)
+  }
+
+  /// Returns a copy of the encoded secret key
+  pub fn to_bytes(&self) -> [u8; CRYPTO_SECRETKEYBYTES]
+  {
+    self.bytes
+  }
+
+  /// Derives the public key by recomputing the root of the top-most 
+  /// subtree from SK_SEED and PUB_SEED
+  /// 
+  /// Unlike [`SigningKey::verifying_key`] this does not trust the root stored
+  /// in the secret key, at the cost of a full subtree computation.
+  pub fn public_key(&self) -> PublicKey
+  {
+    let mut bytes = [0u8; CRYPTO_PUBLICKEYBYTES];
+    bytes[..SPX_N].copy_from_slice(&self.bytes[2*SPX_N..3*SPX_N]);
+    bytes[SPX_N..].copy_from_slice(&spx_gen_root::<Selected>(&self.bytes)[..SPX_N]);
+    PublicKey { bytes }
+  }
+
+  /// Checks that the root stored in the secret key matches the one derived 
+  /// from SK_SEED and PUB_SEED
+  /// 
+  /// A corrupted or tampered secret key produces signatures that never 
+  /// verify, so this is worth running whenever a key is loaded.
+  /// 
+  /// Example: 
+  /// ```no_run
+  /// # use pqc_sphincsplus::*;
+  /// # let keys = keypair().unwrap();
+  /// let sk = SecretKey::from_bytes(keys.secret.as_ref()).unwrap();
+  /// assert!(sk.validate().is_ok());
+  /// ```
+  pub fn validate(&self) -> Result<(), SigError>
+  {
+    let root = spx_gen_root::<Selected>(&self.bytes);
+    if bool::from(root[..SPX_N].ct_eq(&self.bytes[3*SPX_N..])) {
+      Ok(())
+    } else {
+      Err(SigError::CorruptSecretKey)
+    }
+  }
+
+  /// The PUB_SEED || root half embedded at the end of the secret key
+  fn public_half(&self) -> &[u8]
+  {
+    &self.bytes[CRYPTO_SECRETKEYBYTES - CRYPTO_PUBLICKEYBYTES..]
+  }
+
+  fn sign(&self, msg: &[u8], optrand: &[u8]) -> Result<Signature, SigError>
+  {
+    let mut sig = Signature::zeroed();
+    self.sign_into(&mut sig.bytes, msg, optrand)?;
+    Ok(sig)
+  }
+
+  fn sign_into(
+    &self, sig: &mut [u8], mut msg: &[u8], optrand: &[u8]
+  ) -> Result<(), SigError>
+  {
+    self.sign_msg_into(sig, &mut msg, optrand, Variant::Sphincs)
+  }
+
+  fn sign_msg_into(
+    &self, sig: &mut [u8], msg: &mut dyn Message, optrand: &[u8], 
+    variant: Variant
+  ) -> Result<(), SigError>
+  {
+    check_len(sig, CRYPTO_BYTES, SigError::SignatureLength)?;
+    spx_sign_msg::<Selected>(sig, msg, &self.bytes, optrand, variant)
+  }
+
+  /// FIPS 205 signing of the message made of parts
+  #[cfg(any(feature = "slh-dsa", feature = "prehash"))]
+  fn sign_slh_dsa(
+    &self, mut parts: &[&[u8]], mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    let optrand = spx_optrand::<Selected>(&self.bytes, mode)?;
+    let mut sig = Signature::zeroed();
+    self.sign_msg_into(&mut sig.bytes, &mut parts, &optrand, Variant::SlhDsa)?;
+    Ok(sig)
+  }
+
+  #[cfg(feature = "slh-dsa")]
+  fn sign_pure(
+    &self, msg: &[u8], ctx: &[u8], mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    let header = slh_dsa_header(0, ctx)?;
+    self.sign_slh_dsa(&[&header, ctx, msg], mode)
+  }
+
+  #[cfg(feature = "prehash")]
+  fn sign_digest(
+    &self, digest: &[u8], ctx: &[u8], ph: PreHash, mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    let header = prehash_header(ctx, ph, digest)?;
+    self.sign_slh_dsa(&[&header, ctx, ph.oid(), digest], mode)
+  }
+
+  #[cfg(feature = "std")]
+  fn sign_reader<R>(&self, reader: &mut R, mode: SignMode) -> Result<Signature, SigError>
+    where R: Read + Seek
+  {
+    let optrand = spx_optrand::<Selected>(&self.bytes, mode)?;
+    let mut sig = Signature::zeroed();
+    let mut msg = SeekMessage::new(reader)?;
+    self.sign_msg_into(&mut sig.bytes, &mut msg, &optrand, Variant::Sphincs)?;
+    Ok(sig)
+  }
+
+  fn sign_into_with_mode(
+    &self, sig: &mut [u8], msg: &[u8], mode: SignMode
+  ) -> Result<(), SigError>
+  {
+    let optrand = spx_optrand::<Selected>(&self.bytes, mode)?;
+    self.sign_into(sig, msg, &optrand)
+  }
+
+  /// Signs directly into the front of the signed message buffer
+  #[cfg(feature = "alloc")]
+  fn sign_attached<R>(&self, msg: &[u8], rng: &mut R) -> Result<Vec<u8>, SigError>
+    where R: RngCore + CryptoRng
+  {
+    let mut sm = vec![0u8; CRYPTO_BYTES + msg.len()];
+    self.sign_into_with_mode(&mut sm[..CRYPTO_BYTES], msg, SignMode::Hedged(rng))?;
+    sm[CRYPTO_BYTES..].copy_from_slice(msg);
+    Ok(sm)
+  }
+
+  fn sign_with_rng<R>(
+    &self, msg: &[u8], rng: &mut R
+  ) -> Result<Signature, SigError>
+    where R: RngCore + CryptoRng
+  {
+    let mut optrand = [0u8; SPX_N];
+    randombytes(rng, &mut optrand, SPX_N);
+    self.sign(msg, &optrand)
+  }
+
+  fn sign_deterministic(&self, msg: &[u8]) -> Result<Signature, SigError>
+  {
+    self.sign(msg, &spx_deterministic_optrand::<Selected>(&self.bytes))
+  }
+
+  fn sign_with_mode(&self, msg: &[u8], mode: SignMode) -> Result<Signature, SigError>
+  {
+    let optrand = spx_optrand::<Selected>(&self.bytes, mode)?;
+    self.sign(msg, &optrand)
+  }
+}
+
+impl AsRef<[u8]> for SecretKey {
+  fn as_ref(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+impl ConstantTimeEq for SecretKey {
+  fn ct_eq(&self, other: &Self) -> Choice {
+    self.bytes.ct_eq(&other.bytes)
+  }
+}
+
+impl PartialEq for SecretKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.ct_eq(other).into()
+  }
+}
+
+impl Eq for SecretKey {}
+
+impl fmt::Debug for SecretKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("SecretKey([REDACTED])")
+  }
+}
+
+/// Selects the randomness used when signing
+/// 
+/// Every mode produces signatures that verify the same way, they only 
+/// differ in where the SPX_N byte `optrand` value comes from.
+pub enum SignMode<'a> {
+  /// Uses PUB_SEED as optrand, so signing the same message with the same key
+  /// always gives the same signature
+  Deterministic,
+  /// Draws a fresh optrand from the RNG for every signature
+  Hedged(&'a mut dyn CryptoRngCore),
+  /// Uses the caller provided optrand, which must be exactly SPX_N bytes
+  External(&'a [u8])
+}
+
+/// A detached signature
+/// 
+/// With the `alloc` feature the signature bytes live on the heap, otherwise 
+/// they are stored inline. Borrow the bytes with `as_ref()` and use 
+/// [`sign_into`] to sign straight into a caller provided buffer instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+  #[cfg(feature = "alloc")]
+  bytes: Box<[u8]>,
+  #[cfg(not(feature = "alloc"))]
+  bytes: [u8; CRYPTO_BYTES]
+}
+
+impl Signature {
+  /// Creates a signature from exactly CRYPTO_BYTES bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    if bytes.len() != CRYPTO_BYTES {
+      return Err(SigError::SignatureLength);
+    }
+    let mut sig = Self::zeroed();
+    sig.bytes.copy_from_slice(bytes);
+    Ok(sig)
+  }
+
+  /// Converts the signature into its heap allocated bytes without copying
+  #[cfg(feature = "alloc")]
+  pub fn into_vec(self) -> Vec<u8>
+  {
+    self.bytes.into_vec()
+  }
+
+  #[cfg(feature = "alloc")]
+  fn zeroed() -> Self
+  {
+    Self { bytes: vec![0u8; CRYPTO_BYTES].into_boxed_slice() }
+  }
+
+  #[cfg(not(feature = "alloc"))]
+  fn zeroed() -> Self
+  {
+    Self { bytes: [0u8; CRYPTO_BYTES] }
+  }
+}
+
+impl AsRef<[u8]> for Signature {
+  fn as_ref(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Keypair {
-  pub public: [u8; CRYPTO_PUBLICKEYBYTES],
-  pub secret: [u8; CRYPTO_SECRETKEYBYTES]
+  pub public: PublicKey,
+  pub secret: SecretKey
+}
+
+impl Keypair {
+  /// Creates a keypair from its encoded halves
+  ///
+  /// Fails if either length is wrong or if the PUB_SEED || root embedded in
+  /// the secret key does not match the public key. The root itself is not 
+  /// recomputed, see [`SecretKey::from_bytes_checked`].
+  pub fn from_bytes(public: &[u8], secret: &[u8]) -> Result<Self, SigError>
+  {
+    let public = PublicKey::from_bytes(public)?;
+    let secret = SecretKey::from_bytes(secret)?;
+    if secret.public_half() != public.as_ref() {
+      return Err(SigError::KeyMismatch)
+    }
+    Ok(Self { public, secret })
+  }
 }
 
+/// Errors returned by key generation, signing and verification
+///
+/// New variants may be added, and `Io` only exists with the `std` feature, 
+/// so matches need a wildcard arm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SigError {
-  Input,
+  /// The public key is not CRYPTO_PUBLICKEYBYTES long
+  PublicKeyLength,
+  /// The secret key is not CRYPTO_SECRETKEYBYTES long
+  SecretKeyLength,
+  /// The signature is not CRYPTO_BYTES long
+  SignatureLength,
+  /// An output buffer is too short for the result
+  BufferLength,
+  /// A key generation seed has the wrong length
+  SeedLength,
+  /// The signing randomness (optrand) is not SPX_N bytes long
+  OptrandLength,
+  /// The public key does not match the one embedded in the secret key
+  KeyMismatch,
+  /// The root stored in the secret key does not match its SK_SEED and PUB_SEED
+  CorruptSecretKey,
+  /// A newly generated keypair failed its pairwise consistency test
+  PairwiseConsistency,
+  /// A fault was detected while signing and the signature was discarded
+  FaultDetected,
+  /// A context string is longer than 255 bytes
+  ContextLength,
+  /// A pre-hashed digest has the wrong length for its digest algorithm
+  DigestLength,
+  /// No parameter set has the given name
+  UnknownParameterSet,
+  /// Reading the message failed
+  #[cfg(feature = "std")]
+  Io(std::io::ErrorKind),
+  /// The signature is not valid for this message and public key
   Verify
 }
 
+impl fmt::Display for SigError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let msg = match self {
+      SigError::PublicKeyLength => "invalid public key length",
+      SigError::SecretKeyLength => "invalid secret key length",
+      SigError::SignatureLength => "invalid signature length",
+      SigError::BufferLength => "output buffer too short",
+      SigError::SeedLength => "invalid key generation seed length",
+      SigError::OptrandLength => "invalid signing randomness length",
+      SigError::KeyMismatch => "public key does not match secret key",
+      SigError::CorruptSecretKey => "secret key root does not match its seeds",
+      SigError::PairwiseConsistency => "pairwise consistency test failed",
+      SigError::FaultDetected => "fault detected during signing",
+      SigError::ContextLength => "context string longer than 255 bytes",
+      SigError::DigestLength => "invalid pre-hash digest length",
+      SigError::UnknownParameterSet => "unknown parameter set name",
+      #[cfg(feature = "std")]
+      SigError::Io(_) => "reading the message failed",
+      SigError::Verify => "signature verification failed",
+    };
+    f.write_str(msg)
+  }
+}
+
+impl core::error::Error for SigError {}
+
 /// Generates a keypair for signing and verification
 /// 
+/// With the `pct` feature every new keypair must pass a pairwise 
+/// consistency test, otherwise [`SigError::PairwiseConsistency`] is returned.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// let keys = keypair().unwrap();
+/// assert!(keys.public.as_ref().len() == CRYPTO_PUBLICKEYBYTES);
+/// assert!(keys.secret.as_ref().len() == CRYPTO_SECRETKEYBYTES);
+/// ```
+#[cfg(any(feature = "std", feature = "getrandom"))]
+pub fn keypair() -> Result<Keypair, SigError>
+{
+  keypair_with_rng(&mut default_rng())
+}
+
+/// Generates a keypair using the provided random number generator
+/// 
 /// Example: 
 /// ```no_run
 /// # use pqc_sphincsplus::*;
-/// let keys = keypair();
-/// assert!(keys.public.len() == CRYPTO_PUBLICKEYBYTES);
-/// assert!(keys.secret.len() == CRYPTO_SECRETKEYBYTES);
+/// let mut rng = rand::thread_rng();
+/// let keys = keypair_with_rng(&mut rng).unwrap();
 /// ```
-pub fn keypair() -> Keypair
+pub fn keypair_with_rng<R>(rng: &mut R) -> Result<Keypair, SigError>
+  where R: RngCore + CryptoRng
+{
+  let mut seed = [0u8; CRYPTO_SEEDBYTES];
+  randombytes(rng, &mut seed, CRYPTO_SEEDBYTES);
+  keypair_from_seed(&seed)
+}
+
+/// Deterministically generates a keypair from its three SPX_N byte seeds
+/// 
+/// This is `slh_keygen_internal` from FIPS 205, the same seeds always give 
+/// the same keypair. The seeds must come from an approved source of 
+/// randomness and SK_SEED and SK_PRF must be kept secret.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// let n = CRYPTO_SEEDBYTES / 3;
+/// let (sk_seed, sk_prf, pk_seed) = (vec![1u8; n], vec![2u8; n], vec![3u8; n]);
+/// let keys = keypair_from_seeds(&sk_seed, &sk_prf, &pk_seed).unwrap();
+/// assert_eq!(keys, keypair_from_seeds(&sk_seed, &sk_prf, &pk_seed).unwrap());
+/// ```
+pub fn keypair_from_seeds(
+  sk_seed: &[u8], sk_prf: &[u8], pk_seed: &[u8]
+) -> Result<Keypair, SigError>
+{
+  let mut seed = [0u8; CRYPTO_SEEDBYTES];
+  for (i, part) in [sk_seed, sk_prf, pk_seed].iter().enumerate() {
+    if part.len() != SPX_N {
+      return Err(SigError::SeedLength)
+    }
+    seed[i*SPX_N..(i+1)*SPX_N].copy_from_slice(part);
+  }
+  keypair_from_seed(&seed)
+}
+
+fn keypair_from_seed(seed: &[u8; CRYPTO_SEEDBYTES]) -> Result<Keypair, SigError>
 {
   let mut public = [0u8; CRYPTO_PUBLICKEYBYTES];
   let mut secret = [0u8; CRYPTO_SECRETKEYBYTES];
-  crypto_sign_keypair(&mut public, &mut secret, None);
-  Keypair { public, secret }
+  spx_seed_keypair::<Selected>(&mut public, &mut secret, seed)?;
+  Ok(Keypair {
+    public: PublicKey { bytes: public },
+    secret: SecretKey { bytes: secret }
+  })
 }
 
 /// Generates a signature for the given message using a keypair
@@ -34,29 +526,696 @@ pub fn keypair() -> Keypair
 /// Example: 
 /// ```no_run
 /// # use pqc_sphincsplus::*;
-/// # let keys = keypair();
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let sig = sign(&msg, &keys).unwrap();
+/// assert!(sig.as_ref().len() == CRYPTO_BYTES);
+/// ```
+#[cfg(any(feature = "std", feature = "getrandom"))]
+pub fn sign(msg: &[u8], keypair: &Keypair) -> Result<Signature, SigError>
+{
+  sign_with_rng(msg, keypair, &mut default_rng())
+}
+
+/// Generates a signature using the provided random number generator for 
+/// the signing randomness
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let mut rng = rand::thread_rng();
+/// let msg = [0u8; 32];
+/// let sig = sign_with_rng(&msg, &keys, &mut rng).unwrap();
+/// ```
+pub fn sign_with_rng<R>(
+  msg: &[u8], keypair: &Keypair, rng: &mut R
+) -> Result<Signature, SigError>
+  where R: RngCore + CryptoRng
+{
+  keypair.secret.sign_with_rng(msg, rng)
+}
+
+/// Generates a deterministic signature, the same message and keypair 
+/// always produce the same signature
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let sig = sign_deterministic(&msg, &keys).unwrap();
+/// assert_eq!(sig, sign_deterministic(&msg, &keys).unwrap());
+/// ```
+pub fn sign_deterministic(msg: &[u8], keypair: &Keypair) -> Result<Signature, SigError>
+{
+  keypair.secret.sign_deterministic(msg)
+}
+
+/// Generates a signature with the signing randomness chosen by mode
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
 /// let msg = [0u8; 32];
-/// let sig = sign(&msg, &keys);
-/// assert!(sig.len() == CRYPTO_BYTES);
-/// ```  
-pub fn sign(msg: &[u8], keypair: &Keypair) -> [u8; CRYPTO_BYTES] 
+/// // The security parameter n, the seed holds three n byte values
+/// const N: usize = CRYPTO_SEEDBYTES / 3;
+/// let optrand = [7u8; N];
+/// let sig = sign_with_mode(&msg, &keys, SignMode::External(&optrand));
+/// assert!(sig.is_ok());
+/// ```
+pub fn sign_with_mode(
+  msg: &[u8], keypair: &Keypair, mode: SignMode
+) -> Result<Signature, SigError>
+{
+  keypair.secret.sign_with_mode(msg, mode)
+}
+
+/// Signs a message streamed from a reader, from its current position to the 
+/// end, without holding it in memory
+/// 
+/// The reader is read twice, once to derive the signing randomness R and 
+/// once for the message digest, so it has to be seekable.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let mut file = std::fs::File::open("artifact.bin").unwrap();
+/// let sig = sign_reader(&mut file, &keys).unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub fn sign_reader<R>(reader: &mut R, keypair: &Keypair) -> Result<Signature, SigError>
+  where R: Read + Seek
 {
-  let mut sig = [0u8; CRYPTO_BYTES];
-  crypto_sign_signature(&mut sig, msg, &keypair.secret, None);
-  sig
+  sign_reader_with_mode(reader, keypair, SignMode::Hedged(&mut default_rng()))
 }
 
-/// Verify signature using keypair
+/// Signs a message streamed from a reader with the signing randomness chosen 
+/// by mode, see [`sign_reader`]
+#[cfg(feature = "std")]
+pub fn sign_reader_with_mode<R>(
+  reader: &mut R, keypair: &Keypair, mode: SignMode
+) -> Result<Signature, SigError>
+  where R: Read + Seek
+{
+  keypair.secret.sign_reader(reader, mode)
+}
+
+/// Verifies a signature over a message streamed from a reader
+/// 
+/// R is part of the signature so the reader is only read once and does not 
+/// need to be seekable.
 /// 
 /// Example: 
 /// ```no_run
 /// # use pqc_sphincsplus::*;
-/// # let keys = keypair();
+/// # let keys = keypair().unwrap();
+/// # let sig = sign(&[], &keys).unwrap();
+/// let mut file = std::fs::File::open("artifact.bin").unwrap();
+/// assert!(verify_reader(sig.as_ref(), &mut file, &keys.public).is_ok());
+/// ```
+#[cfg(feature = "std")]
+pub fn verify_reader<R: Read>(
+  sig: &[u8], reader: &mut R, public: &PublicKey
+) -> Result<(), SigError>
+{
+  public.verify_reader(sig, reader)
+}
+
+/// Generates a FIPS 205 SLH-DSA signature of the message under a context 
+/// string of at most 255 bytes, which may be empty
+/// 
+/// The signature covers 0x00 || len(ctx) || ctx || msg and uses the FIPS 205 
+/// message encoding, it does not verify as a SPHINCS+ 3.1 signature from 
+/// [`sign`] and vice versa. FIPS 205 only defines the sha2 and shake simple 
+/// parameter sets, with haraka or robust the encoding is the same but no 
+/// other implementation will interoperate.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let sig = slh_dsa_sign(&msg, b"my app", &keys).unwrap();
+/// assert!(slh_dsa_verify(sig.as_ref(), &msg, b"my app", &keys.public).is_ok());
+/// ```
+#[cfg(all(feature = "slh-dsa", any(feature = "std", feature = "getrandom")))]
+pub fn slh_dsa_sign(
+  msg: &[u8], ctx: &[u8], keypair: &Keypair
+) -> Result<Signature, SigError>
+{
+  slh_dsa_sign_with_mode(msg, ctx, keypair, SignMode::Hedged(&mut default_rng()))
+}
+
+/// Generates a FIPS 205 SLH-DSA signature with the signing randomness chosen 
+/// by mode, see [`slh_dsa_sign`]
+#[cfg(feature = "slh-dsa")]
+pub fn slh_dsa_sign_with_mode(
+  msg: &[u8], ctx: &[u8], keypair: &Keypair, mode: SignMode
+) -> Result<Signature, SigError>
+{
+  keypair.secret.sign_pure(msg, ctx, mode)
+}
+
+/// Verifies a FIPS 205 SLH-DSA signature, see [`slh_dsa_sign`]
+#[cfg(feature = "slh-dsa")]
+pub fn slh_dsa_verify(
+  sig: &[u8], msg: &[u8], ctx: &[u8], public: &PublicKey
+) -> Result<(), SigError>
+{
+  public.verify_pure(sig, msg, ctx)
+}
+
+/// Generates a pre-hash (HashSLH-DSA) signature, the message is hashed with 
+/// ph and the signature covers 0x01 || len(ctx) || ctx || OID || PH(msg)
+/// 
+/// The context string binds the signature to an application and may be 
+/// empty, it is at most 255 bytes.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let sig = sign_prehash(&msg, b"my app", PreHash::Sha512, &keys).unwrap();
+/// assert!(verify_prehash(sig.as_ref(), &msg, b"my app", PreHash::Sha512, &keys.public).is_ok());
+/// ```
+#[cfg(all(feature = "prehash", any(feature = "std", feature = "getrandom")))]
+pub fn sign_prehash(
+  msg: &[u8], ctx: &[u8], ph: PreHash, keypair: &Keypair
+) -> Result<Signature, SigError>
+{
+  sign_prehash_with_mode(msg, ctx, ph, keypair, SignMode::Hedged(&mut default_rng()))
+}
+
+/// Generates a pre-hash signature with the signing randomness chosen by 
+/// mode, see [`sign_prehash`]
+#[cfg(feature = "prehash")]
+pub fn sign_prehash_with_mode(
+  msg: &[u8], ctx: &[u8], ph: PreHash, keypair: &Keypair, mode: SignMode
+) -> Result<Signature, SigError>
+{
+  let mut digest = [0u8; MAX_DIGEST_BYTES];
+  let len = ph.digest(msg, &mut digest);
+  keypair.secret.sign_digest(&digest[..len], ctx, ph, mode)
+}
+
+/// Generates a pre-hash signature over a digest computed elsewhere, which 
+/// must be ph.digest_len() bytes, see [`sign_prehash`]
+#[cfg(feature = "prehash")]
+pub fn sign_digest_with_mode(
+  digest: &[u8], ctx: &[u8], ph: PreHash, keypair: &Keypair, mode: SignMode
+) -> Result<Signature, SigError>
+{
+  keypair.secret.sign_digest(digest, ctx, ph, mode)
+}
+
+/// Verifies a pre-hash signature over the message, see [`sign_prehash`]
+#[cfg(feature = "prehash")]
+pub fn verify_prehash(
+  sig: &[u8], msg: &[u8], ctx: &[u8], ph: PreHash, public: &PublicKey
+) -> Result<(), SigError>
+{
+  let mut digest = [0u8; MAX_DIGEST_BYTES];
+  let len = ph.digest(msg, &mut digest);
+  public.verify_digest(sig, &digest[..len], ctx, ph)
+}
+
+/// Verifies a pre-hash signature against a digest computed elsewhere
+#[cfg(feature = "prehash")]
+pub fn verify_digest(
+  sig: &[u8], digest: &[u8], ctx: &[u8], ph: PreHash, public: &PublicKey
+) -> Result<(), SigError>
+{
+  public.verify_digest(sig, digest, ctx, ph)
+}
+
+/// Signs into a caller provided buffer of exactly CRYPTO_BYTES bytes, 
+/// avoiding a large signature on the stack
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let mut sig = vec![0u8; CRYPTO_BYTES];
+/// sign_into(&mut sig, &msg, &keys).unwrap();
+/// assert!(verify(&sig, &msg, &keys.public).is_ok());
+/// ```
+#[cfg(any(feature = "std", feature = "getrandom"))]
+pub fn sign_into(sig: &mut [u8], msg: &[u8], keypair: &Keypair) -> Result<(), SigError>
+{
+  sign_into_with_mode(sig, msg, keypair, SignMode::Hedged(&mut default_rng()))
+}
+
+/// Signs into a caller provided buffer with the signing randomness chosen 
+/// by mode, see [`sign_into`]
+pub fn sign_into_with_mode(
+  sig: &mut [u8], msg: &[u8], keypair: &Keypair, mode: SignMode
+) -> Result<(), SigError>
+{
+  keypair.secret.sign_into_with_mode(sig, msg, mode)
+}
+
+/// Verify signature using the public key
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
 /// # let msg = [0u8; 32];
-/// # let sig = sign(&msg, &keys);
-/// let sig_verify = verify(&sig, &msg, &keys);
+/// # let sig = sign(&msg, &keys).unwrap();
+/// let sig_verify = verify(sig.as_ref(), &msg, &keys.public);
 /// assert!(sig_verify.is_ok());
-pub fn verify(sig: &[u8], msg: &[u8], keypair: &Keypair) -> Result<(), SigError>
+pub fn verify(sig: &[u8], msg: &[u8], public: &PublicKey) -> Result<(), SigError>
+{
+  crypto_sign_verify(sig, msg, &public.bytes)
+}
+
+/// Generates an attached signature, the signed message sm = sig || msg
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// let msg = [0u8; 32];
+/// let sm = sign_attached(&msg, &keys).unwrap();
+/// assert!(sm.len() == CRYPTO_BYTES + msg.len());
+/// ```
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "getrandom")))]
+pub fn sign_attached(msg: &[u8], keypair: &Keypair) -> Result<Vec<u8>, SigError>
+{
+  sign_attached_with_rng(msg, keypair, &mut default_rng())
+}
+
+/// Generates an attached signature using the provided random number 
+/// generator for the signing randomness
+#[cfg(feature = "alloc")]
+pub fn sign_attached_with_rng<R>(
+  msg: &[u8], keypair: &Keypair, rng: &mut R
+) -> Result<Vec<u8>, SigError>
+  where R: RngCore + CryptoRng
 {
-  crypto_sign_verify(&sig, &msg, &keypair.public)
-}
\ No newline at end of file
+  keypair.secret.sign_attached(msg, rng)
+}
+
+/// Verifies an attached signature and returns the embedded message
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// # let sm = sign_attached(&[0u8; 32], &keys).unwrap();
+/// let msg = open(&sm, &keys.public).unwrap();
+/// assert_eq!(msg, &[0u8; 32]);
+/// ```
+pub fn open<'a>(sm: &'a [u8], public: &PublicKey) -> Result<&'a [u8], SigError>
+{
+  public.open(sm)
+}
+
+/// A public key that can only be used to verify signatures
+/// 
+/// Contains no secret material, so it is safe to hand to verifiers. Like 
+/// the free functions, its methods take the signature first.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// # let msg = [0u8; 32];
+/// # let sig = sign(&msg, &keys).unwrap();
+/// let vk = VerifyingKey::from_bytes(keys.public.as_ref()).unwrap();
+/// assert!(vk.verify(sig.as_ref(), &msg).is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKey {
+  public: PublicKey
+}
+
+impl VerifyingKey {
+  /// Creates a verifying key from a CRYPTO_PUBLICKEYBYTES long public key
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    Ok(Self { public: PublicKey::from_bytes(bytes)? })
+  }
+
+  /// Returns the encoded public key
+  pub fn as_bytes(&self) -> &[u8; CRYPTO_PUBLICKEYBYTES]
+  {
+    &self.public.bytes
+  }
+
+  /// Returns the underlying public key
+  pub fn public_key(&self) -> &PublicKey
+  {
+    &self.public
+  }
+
+  /// Verifies a detached signature over the message
+  pub fn verify(&self, sig: &[u8], msg: &[u8]) -> Result<(), SigError>
+  {
+    crypto_sign_verify(sig, msg, &self.public.bytes)
+  }
+
+  /// Verifies an attached signature sm = sig || msg and returns msg
+  pub fn open<'a>(&self, sm: &'a [u8]) -> Result<&'a [u8], SigError>
+  {
+    self.public.open(sm)
+  }
+
+  /// Verifies a FIPS 205 SLH-DSA signature, see [`slh_dsa_sign`]
+  #[cfg(feature = "slh-dsa")]
+  pub fn slh_dsa_verify(
+    &self, sig: &[u8], msg: &[u8], ctx: &[u8]
+  ) -> Result<(), SigError>
+  {
+    self.public.verify_pure(sig, msg, ctx)
+  }
+
+  /// Verifies a pre-hash signature over the message, see [`sign_prehash`]
+  #[cfg(feature = "prehash")]
+  pub fn verify_prehash(
+    &self, sig: &[u8], msg: &[u8], ctx: &[u8], ph: PreHash
+  ) -> Result<(), SigError>
+  {
+    let mut digest = [0u8; MAX_DIGEST_BYTES];
+    let len = ph.digest(msg, &mut digest);
+    self.public.verify_digest(sig, &digest[..len], ctx, ph)
+  }
+
+  /// Verifies a pre-hash signature against a digest computed elsewhere
+  #[cfg(feature = "prehash")]
+  pub fn verify_digest(
+    &self, sig: &[u8], digest: &[u8], ctx: &[u8], ph: PreHash
+  ) -> Result<(), SigError>
+  {
+    self.public.verify_digest(sig, digest, ctx, ph)
+  }
+
+  /// Starts an incremental verification of sig, see [`Verifier`]
+  pub fn verifier<'a>(&self, sig: &'a [u8]) -> Result<Verifier<'a>, SigError>
+  {
+    Verifier::new(&self.public, sig)
+  }
+
+  /// Verifies a detached signature over a message streamed from a reader, 
+  /// see [`verify_reader`]
+  #[cfg(feature = "std")]
+  pub fn verify_reader<R: Read>(&self, sig: &[u8], reader: &mut R) -> Result<(), SigError>
+  {
+    self.public.verify_reader(sig, reader)
+  }
+}
+
+/// Verifies a signature over a message that arrives in chunks
+/// 
+/// R is the first n bytes of the signature, so the message digest is 
+/// computed as the chunks arrive and the message never has to be buffered.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// # let keys = keypair().unwrap();
+/// # let sig = sign(b"hello world", &keys).unwrap();
+/// let mut verifier = Verifier::new(&keys.public, sig.as_ref()).unwrap();
+/// verifier.update(b"hello ");
+/// verifier.update(b"world");
+/// assert!(verifier.finalize().is_ok());
+/// ```
+pub struct Verifier<'a> {
+  inner: SpxVerifier<'a, Selected>
+}
+
+impl<'a> Verifier<'a> {
+  /// Starts verifying sig under the public key
+  pub fn new(public: &PublicKey, sig: &'a [u8]) -> Result<Self, SigError>
+  {
+    check_len(sig, CRYPTO_BYTES, SigError::SignatureLength)?;
+    Ok(Self { inner: SpxVerifier::new(sig, &public.bytes, Variant::Sphincs) })
+  }
+
+  /// Absorbs the next chunk of the message
+  pub fn update(&mut self, chunk: &[u8])
+  {
+    self.inner.update(chunk);
+  }
+
+  /// Checks the signature against the message absorbed so far
+  pub fn finalize(self) -> Result<(), SigError>
+  {
+    self.inner.finalize()
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for Verifier<'_> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+/// A secret key used to sign messages
+/// 
+/// The matching [`VerifyingKey`] is embedded in the secret key and can be 
+/// extracted to distribute to verifiers.
+/// 
+/// Example: 
+/// ```no_run
+/// # use pqc_sphincsplus::*;
+/// let sk = SigningKey::generate().unwrap();
+/// let msg = [0u8; 32];
+/// let sig = sk.sign(&msg).unwrap();
+/// assert!(sk.verifying_key().verify(sig.as_ref(), &msg).is_ok());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SigningKey {
+  secret: SecretKey
+}
+
+impl SigningKey {
+  /// Generates a new random signing key
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn generate() -> Result<Self, SigError>
+  {
+    Self::generate_with_rng(&mut default_rng())
+  }
+
+  /// Generates a new signing key using the provided random number generator
+  pub fn generate_with_rng<R>(rng: &mut R) -> Result<Self, SigError>
+    where R: RngCore + CryptoRng
+  {
+    Ok(Self { secret: keypair_with_rng(rng)?.secret })
+  }
+
+  /// Deterministically derives a signing key from its seeds, see 
+  /// [`keypair_from_seeds`]
+  pub fn from_seeds(
+    sk_seed: &[u8], sk_prf: &[u8], pk_seed: &[u8]
+  ) -> Result<Self, SigError>
+  {
+    Ok(Self { secret: keypair_from_seeds(sk_seed, sk_prf, pk_seed)?.secret })
+  }
+
+  /// Creates a signing key from a CRYPTO_SECRETKEYBYTES long secret key, 
+  /// only checking its length
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    Ok(Self { secret: SecretKey::from_bytes(bytes)? })
+  }
+
+  /// Creates a signing key from a CRYPTO_SECRETKEYBYTES long secret key 
+  /// and checks its structure, see [`SecretKey::from_bytes_checked`]
+  pub fn from_bytes_checked(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    Ok(Self { secret: SecretKey::from_bytes_checked(bytes)? })
+  }
+
+  /// Returns the encoded secret key
+  pub fn as_bytes(&self) -> &[u8; CRYPTO_SECRETKEYBYTES]
+  {
+    &self.secret.bytes
+  }
+
+  /// Returns the underlying secret key
+  pub fn secret_key(&self) -> &SecretKey
+  {
+    &self.secret
+  }
+
+  /// Checks the integrity of the secret key, see [`SecretKey::validate`]
+  pub fn validate(&self) -> Result<(), SigError>
+  {
+    self.secret.validate()
+  }
+
+  /// Returns the verifying key for this signing key
+  pub fn verifying_key(&self) -> VerifyingKey
+  {
+    let mut bytes = [0u8; CRYPTO_PUBLICKEYBYTES];
+    bytes.copy_from_slice(self.secret.public_half());
+    VerifyingKey { public: PublicKey { bytes } }
+  }
+
+  /// Generates a detached signature for the given message
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn sign(&self, msg: &[u8]) -> Result<Signature, SigError>
+  {
+    self.sign_with_rng(msg, &mut default_rng())
+  }
+
+  /// Generates a detached signature using the provided random number 
+  /// generator for the signing randomness
+  pub fn sign_with_rng<R>(
+    &self, msg: &[u8], rng: &mut R
+  ) -> Result<Signature, SigError>
+    where R: RngCore + CryptoRng
+  {
+    self.secret.sign_with_rng(msg, rng)
+  }
+
+  /// Generates a deterministic detached signature, see [`sign_deterministic`]
+  pub fn sign_deterministic(&self, msg: &[u8]) -> Result<Signature, SigError>
+  {
+    self.secret.sign_deterministic(msg)
+  }
+
+  /// Generates a detached signature with the signing randomness chosen by mode
+  pub fn sign_with_mode(
+    &self, msg: &[u8], mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    self.secret.sign_with_mode(msg, mode)
+  }
+
+  /// Signs into a caller provided buffer of exactly CRYPTO_BYTES bytes
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn sign_into(&self, sig: &mut [u8], msg: &[u8]) -> Result<(), SigError>
+  {
+    self.sign_into_with_mode(sig, msg, SignMode::Hedged(&mut default_rng()))
+  }
+
+  /// Signs into a caller provided buffer with the signing randomness chosen 
+  /// by mode
+  pub fn sign_into_with_mode(
+    &self, sig: &mut [u8], msg: &[u8], mode: SignMode
+  ) -> Result<(), SigError>
+  {
+    self.secret.sign_into_with_mode(sig, msg, mode)
+  }
+
+  /// Generates a FIPS 205 SLH-DSA signature, see [`slh_dsa_sign`]
+  #[cfg(all(feature = "slh-dsa", any(feature = "std", feature = "getrandom")))]
+  pub fn slh_dsa_sign(&self, msg: &[u8], ctx: &[u8]) -> Result<Signature, SigError>
+  {
+    self.slh_dsa_sign_with_mode(msg, ctx, SignMode::Hedged(&mut default_rng()))
+  }
+
+  /// Generates a FIPS 205 SLH-DSA signature with the signing randomness 
+  /// chosen by mode
+  #[cfg(feature = "slh-dsa")]
+  pub fn slh_dsa_sign_with_mode(
+    &self, msg: &[u8], ctx: &[u8], mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    self.secret.sign_pure(msg, ctx, mode)
+  }
+
+  /// Generates a pre-hash signature, see [`sign_prehash`]
+  #[cfg(all(feature = "prehash", any(feature = "std", feature = "getrandom")))]
+  pub fn sign_prehash(
+    &self, msg: &[u8], ctx: &[u8], ph: PreHash
+  ) -> Result<Signature, SigError>
+  {
+    self.sign_prehash_with_mode(msg, ctx, ph, SignMode::Hedged(&mut default_rng()))
+  }
+
+  /// Generates a pre-hash signature with the signing randomness chosen by 
+  /// mode
+  #[cfg(feature = "prehash")]
+  pub fn sign_prehash_with_mode(
+    &self, msg: &[u8], ctx: &[u8], ph: PreHash, mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    let mut digest = [0u8; MAX_DIGEST_BYTES];
+    let len = ph.digest(msg, &mut digest);
+    self.secret.sign_digest(&digest[..len], ctx, ph, mode)
+  }
+
+  /// Generates a pre-hash signature over a digest computed elsewhere
+  #[cfg(feature = "prehash")]
+  pub fn sign_digest_with_mode(
+    &self, digest: &[u8], ctx: &[u8], ph: PreHash, mode: SignMode
+  ) -> Result<Signature, SigError>
+  {
+    self.secret.sign_digest(digest, ctx, ph, mode)
+  }
+
+  /// Signs a message streamed from a seekable reader, see [`sign_reader`]
+  #[cfg(feature = "std")]
+  pub fn sign_reader<R>(&self, reader: &mut R) -> Result<Signature, SigError>
+    where R: Read + Seek
+  {
+    self.secret.sign_reader(reader, SignMode::Hedged(&mut default_rng()))
+  }
+
+  /// Signs a message streamed from a seekable reader with the signing 
+  /// randomness chosen by mode
+  #[cfg(feature = "std")]
+  pub fn sign_reader_with_mode<R>(
+    &self, reader: &mut R, mode: SignMode
+  ) -> Result<Signature, SigError>
+    where R: Read + Seek
+  {
+    self.secret.sign_reader(reader, mode)
+  }
+
+  /// Generates an attached signature sm = sig || msg
+  #[cfg(all(feature = "alloc", any(feature = "std", feature = "getrandom")))]
+  pub fn sign_attached(&self, msg: &[u8]) -> Result<Vec<u8>, SigError>
+  {
+    self.sign_attached_with_rng(msg, &mut default_rng())
+  }
+
+  /// Generates an attached signature using the provided random number 
+  /// generator for the signing randomness
+  #[cfg(feature = "alloc")]
+  pub fn sign_attached_with_rng<R>(
+    &self, msg: &[u8], rng: &mut R
+  ) -> Result<Vec<u8>, SigError>
+    where R: RngCore + CryptoRng
+  {
+    self.secret.sign_attached(msg, rng)
+  }
+}
+
+impl From<SecretKey> for SigningKey {
+  fn from(secret: SecretKey) -> Self {
+    Self { secret }
+  }
+}
+
+impl From<PublicKey> for VerifyingKey {
+  fn from(public: PublicKey) -> Self {
+    Self { public }
+  }
+}
+
+impl From<&Keypair> for SigningKey {
+  fn from(keypair: &Keypair) -> Self {
+    Self { secret: keypair.secret.clone() }
+  }
+}
+
+impl From<&Keypair> for VerifyingKey {
+  fn from(keypair: &Keypair) -> Self {
+    Self { public: keypair.public }
+  }
+}
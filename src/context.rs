@@ -1,39 +1,48 @@
-use crate::params::SPX_N;
+use crate::params::SPX_MAX_N;
+
+/// The standard a signature follows, they only differ in how the message 
+/// digest is split into FORS indices
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+  /// SPHINCS+ round 3.1
+  #[default]
+  Sphincs,
+  /// FIPS 205 SLH-DSA
+  #[cfg(any(feature = "slh-dsa", feature = "prehash"))]
+  SlhDsa
+}
 
 /// Sphincs context
+/// 
+/// The seeds hold the SPX_N bytes of the parameter set in use, the hash 
+/// specific fields are only filled in by their own hash function.
 pub struct SpxCtx {
-  pub pub_seed: [u8; SPX_N],
-  pub sk_seed: [u8; SPX_N],
+  pub pub_seed: [u8; SPX_MAX_N],
+  pub sk_seed: [u8; SPX_MAX_N],
+  pub variant: Variant,
   
-  #[cfg(feature="sha2")]
   pub state_seeded: [u8; 40],
   
-  #[cfg(all(feature="sha2", not(any(feature="f128", feature="s128"))))]
   pub state_seeded_512: [u8; 72],
   
-  #[cfg(feature="haraka")]
   pub tweaked512_rc64: [[u64; 8]; 10],
 
-  #[cfg(feature="haraka")]
   pub tweaked256_rc32: [[u32; 8]; 10],
 }
 
 impl Default for SpxCtx {
   fn default() -> Self {
       Self { 
-        pub_seed: [0u8; SPX_N], 
-        sk_seed: [0u8; SPX_N],
+        pub_seed: [0u8; SPX_MAX_N], 
+        sk_seed: [0u8; SPX_MAX_N],
+        variant: Variant::default(),
 
-        #[cfg(feature="sha2")]
         state_seeded: [0u8; 40],
 
-        #[cfg(all(feature="sha2", not(any(feature="f128", feature="s128"))))]
         state_seeded_512: [0u8; 72],
 
-        #[cfg(feature="haraka")]
         tweaked512_rc64: [[0u64; 8]; 10], 
 
-        #[cfg(feature="haraka")]
         tweaked256_rc32: [[0u32; 8]; 10] 
     }
   }
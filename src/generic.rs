@@ -0,0 +1,360 @@
+//! Keys and signatures typed by their parameter set
+//!
+//! The parameter set is a type parameter, so keys and signatures of
+//! different parameter sets cannot be mixed up. Every parameter set has a
+//! marker type, whichever one the cargo features select, and others can be
+//! defined with [`custom_parameter_set`](crate::custom_parameter_set).
+//!
+//! Example:
+//! ```no_run
+//! use pqc_sphincsplus::generic::*;
+//! let sk = SigningKey::<Shake128sSimple>::generate().unwrap();
+//! let sig = sk.sign(b"hello").unwrap();
+//! assert!(sk.verifying_key().verify(&sig, b"hello").is_ok());
+//! ```
+//!
+//! A signature of another parameter set does not compile:
+//! ```compile_fail
+//! use pqc_sphincsplus::generic::*;
+//! let sk = SigningKey::<Shake128sSimple>::generate().unwrap();
+//! let vk = SigningKey::<Sha2_256fSimple>::generate().unwrap().verifying_key();
+//! vk.verify(&sk.sign(b"hello").unwrap(), b"hello");
+//! ```
+//!
+//! `Signature` is heap allocated and needs the `alloc` feature, its size
+//! depends on the parameter set. Without `alloc`, sign into a buffer of
+//! P::BYTES with [`SigningKey::sign_into_with_mode`] and check it with
+//! [`VerifyingKey::verify_bytes`].
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec, vec::Vec};
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use crate::api::{SigError, SignMode};
+use crate::context::Variant;
+use crate::params::SPX_MAX_N;
+use crate::randombytes::*;
+use crate::sign::*;
+
+pub use crate::params::{ParameterSet, HashFamily, Thash};
+pub use crate::params::{
+  Haraka128fRobust, Haraka128fSimple, Haraka128sRobust, Haraka128sSimple,
+  Haraka192fRobust, Haraka192fSimple, Haraka192sRobust, Haraka192sSimple,
+  Haraka256fRobust, Haraka256fSimple, Haraka256sRobust, Haraka256sSimple,
+  Sha2_128fRobust, Sha2_128fSimple, Sha2_128sRobust, Sha2_128sSimple,
+  Sha2_192fRobust, Sha2_192fSimple, Sha2_192sRobust, Sha2_192sSimple,
+  Sha2_256fRobust, Sha2_256fSimple, Sha2_256sRobust, Sha2_256sSimple,
+  Shake128fRobust, Shake128fSimple, Shake128sRobust, Shake128sSimple,
+  Shake192fRobust, Shake192fSimple, Shake192sRobust, Shake192sSimple,
+  Shake256fRobust, Shake256fSimple, Shake256sRobust, Shake256sSimple,
+};
+
+/// A secret key of the parameter set P, formatted as
+/// [SK_SEED || SK_PRF || PUB_SEED || root]
+///
+/// Its `Debug` output is redacted and equality is checked in constant time.
+pub struct SigningKey<P: ParameterSet> {
+  bytes: [u8; 4*SPX_MAX_N],
+  params: PhantomData<P>
+}
+
+impl<P: ParameterSet> SigningKey<P> {
+  /// Generates a new random signing key
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn generate() -> Result<Self, SigError>
+  {
+    Self::generate_with_rng(&mut default_rng())
+  }
+
+  /// Generates a new signing key using the provided random number generator
+  pub fn generate_with_rng<R>(rng: &mut R) -> Result<Self, SigError>
+    where R: RngCore + CryptoRng
+  {
+    let mut seed = [0u8; 3*SPX_MAX_N];
+    randombytes(rng, &mut seed, 3*P::N);
+    Self::from_seed(&seed[..3*P::N])
+  }
+
+  /// Deterministically derives a signing key from its three n byte seeds,
+  /// see [`crate::keypair_from_seeds`]
+  pub fn from_seeds(
+    sk_seed: &[u8], sk_prf: &[u8], pk_seed: &[u8]
+  ) -> Result<Self, SigError>
+  {
+    let mut seed = [0u8; 3*SPX_MAX_N];
+    for (i, part) in [sk_seed, sk_prf, pk_seed].iter().enumerate() {
+      check_len(part, P::N, SigError::SeedLength)?;
+      seed[i*P::N..(i+1)*P::N].copy_from_slice(part);
+    }
+    Self::from_seed(&seed[..3*P::N])
+  }
+
+  fn from_seed(seed: &[u8]) -> Result<Self, SigError>
+  {
+    let mut sk = Self { bytes: [0u8; 4*SPX_MAX_N], params: PhantomData };
+    let mut pk = [0u8; 2*SPX_MAX_N];
+    spx_seed_keypair::<P>(&mut pk[..P::PK_BYTES], &mut sk.bytes[..P::SK_BYTES], seed)?;
+    Ok(sk)
+  }
+
+  /// Creates a signing key from exactly P::SK_BYTES bytes, only checking 
+  /// the length
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    check_len(bytes, P::SK_BYTES, SigError::SecretKeyLength)?;
+    let mut sk = Self { bytes: [0u8; 4*SPX_MAX_N], params: PhantomData };
+    sk.bytes[..P::SK_BYTES].copy_from_slice(bytes);
+    Ok(sk)
+  }
+
+  /// Creates a signing key from exactly P::SK_BYTES bytes and checks its 
+  /// stored root, see [`crate::SecretKey::from_bytes_checked`]
+  pub fn from_bytes_checked(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    let sk = Self::from_bytes(bytes)?;
+    sk.validate()?;
+    Ok(sk)
+  }
+
+  /// Returns the encoded secret key
+  pub fn as_bytes(&self) -> &[u8]
+  {
+    &self.bytes[..P::SK_BYTES]
+  }
+
+  /// Checks that the root stored in the secret key matches the one derived
+  /// from SK_SEED and PUB_SEED, see [`crate::SecretKey::validate`]
+  pub fn validate(&self) -> Result<(), SigError>
+  {
+    let root = spx_gen_root::<P>(self.as_bytes());
+    if bool::from(root[..P::N].ct_eq(&self.bytes[3*P::N..4*P::N])) {
+      Ok(())
+    } else {
+      Err(SigError::CorruptSecretKey)
+    }
+  }
+
+  /// Returns the verifying key embedded in this signing key
+  pub fn verifying_key(&self) -> VerifyingKey<P>
+  {
+    let mut vk = VerifyingKey { bytes: [0u8; 2*SPX_MAX_N], params: PhantomData };
+    vk.bytes[..P::PK_BYTES].copy_from_slice(&self.bytes[2*P::N..4*P::N]);
+    vk
+  }
+
+  /// Generates a detached signature for the given message
+  #[cfg(all(feature = "alloc", any(feature = "std", feature = "getrandom")))]
+  pub fn sign(&self, msg: &[u8]) -> Result<Signature<P>, SigError>
+  {
+    self.sign_with_mode(msg, SignMode::Hedged(&mut default_rng()))
+  }
+
+  /// Generates a detached signature using the provided random number
+  /// generator for the signing randomness
+  #[cfg(feature = "alloc")]
+  pub fn sign_with_rng<R>(
+    &self, msg: &[u8], rng: &mut R
+  ) -> Result<Signature<P>, SigError>
+    where R: RngCore + CryptoRng
+  {
+    self.sign_with_mode(msg, SignMode::Hedged(rng))
+  }
+
+  /// Generates a deterministic detached signature, see
+  /// [`crate::sign_deterministic`]
+  #[cfg(feature = "alloc")]
+  pub fn sign_deterministic(&self, msg: &[u8]) -> Result<Signature<P>, SigError>
+  {
+    self.sign_with_mode(msg, SignMode::Deterministic)
+  }
+
+  /// Generates a detached signature with the signing randomness chosen by mode
+  #[cfg(feature = "alloc")]
+  pub fn sign_with_mode(
+    &self, msg: &[u8], mode: SignMode
+  ) -> Result<Signature<P>, SigError>
+  {
+    let mut sig = Signature::zeroed();
+    self.sign_into_with_mode(sig.bytes_mut(), msg, mode)?;
+    Ok(sig)
+  }
+
+  /// Signs into a caller provided buffer of exactly P::BYTES bytes with the
+  /// signing randomness chosen by mode
+  pub fn sign_into_with_mode(
+    &self, sig: &mut [u8], mut msg: &[u8], mode: SignMode
+  ) -> Result<(), SigError>
+  {
+    check_len(sig, P::BYTES, SigError::SignatureLength)?;
+    let optrand = spx_optrand::<P>(self.as_bytes(), mode)?;
+    spx_sign_msg::<P>(sig, &mut msg, self.as_bytes(), &optrand, Variant::Sphincs)
+  }
+}
+
+impl<P: ParameterSet> Clone for SigningKey<P> {
+  fn clone(&self) -> Self {
+    Self { bytes: self.bytes, params: PhantomData }
+  }
+}
+
+impl<P: ParameterSet> AsRef<[u8]> for SigningKey<P> {
+  fn as_ref(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl<P: ParameterSet> ConstantTimeEq for SigningKey<P> {
+  fn ct_eq(&self, other: &Self) -> Choice {
+    self.bytes.ct_eq(&other.bytes)
+  }
+}
+
+impl<P: ParameterSet> PartialEq for SigningKey<P> {
+  fn eq(&self, other: &Self) -> bool {
+    self.ct_eq(other).into()
+  }
+}
+
+impl<P: ParameterSet> Eq for SigningKey<P> {}
+
+impl<P: ParameterSet> fmt::Debug for SigningKey<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("SigningKey([REDACTED])")
+  }
+}
+
+/// A public key of the parameter set P, formatted as [PUB_SEED || root]
+pub struct VerifyingKey<P: ParameterSet> {
+  bytes: [u8; 2*SPX_MAX_N],
+  params: PhantomData<P>
+}
+
+impl<P: ParameterSet> VerifyingKey<P> {
+  /// Creates a verifying key from exactly P::PK_BYTES bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    check_len(bytes, P::PK_BYTES, SigError::PublicKeyLength)?;
+    let mut vk = Self { bytes: [0u8; 2*SPX_MAX_N], params: PhantomData };
+    vk.bytes[..P::PK_BYTES].copy_from_slice(bytes);
+    Ok(vk)
+  }
+
+  /// Returns the encoded public key
+  pub fn as_bytes(&self) -> &[u8]
+  {
+    &self.bytes[..P::PK_BYTES]
+  }
+
+  /// Verifies a detached signature over the message
+  #[cfg(feature = "alloc")]
+  pub fn verify(&self, sig: &Signature<P>, msg: &[u8]) -> Result<(), SigError>
+  {
+    self.verify_bytes(sig.as_ref(), msg)
+  }
+
+  /// Verifies a detached signature of exactly P::BYTES bytes over the 
+  /// message, for signatures made with [`SigningKey::sign_into_with_mode`]
+  pub fn verify_bytes(&self, sig: &[u8], msg: &[u8]) -> Result<(), SigError>
+  {
+    check_len(sig, P::BYTES, SigError::SignatureLength)?;
+    spx_verify::<P>(sig, msg, self.as_bytes())
+  }
+}
+
+impl<P: ParameterSet> Copy for VerifyingKey<P> {}
+
+impl<P: ParameterSet> Clone for VerifyingKey<P> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<P: ParameterSet> AsRef<[u8]> for VerifyingKey<P> {
+  fn as_ref(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl<P: ParameterSet> PartialEq for VerifyingKey<P> {
+  fn eq(&self, other: &Self) -> bool {
+    self.bytes == other.bytes
+  }
+}
+
+impl<P: ParameterSet> Eq for VerifyingKey<P> {}
+
+impl<P: ParameterSet> fmt::Debug for VerifyingKey<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("VerifyingKey").field(&self.as_bytes()).finish()
+  }
+}
+
+/// A detached signature of the parameter set P
+///
+/// The signature bytes live on the heap, an inline buffer would have to fit
+/// the largest parameter set whichever P is.
+#[cfg(feature = "alloc")]
+pub struct Signature<P: ParameterSet> {
+  bytes: Box<[u8]>,
+  params: PhantomData<P>
+}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> Signature<P> {
+  /// Creates a signature from exactly P::BYTES bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigError>
+  {
+    check_len(bytes, P::BYTES, SigError::SignatureLength)?;
+    let mut sig = Self::zeroed();
+    sig.bytes_mut().copy_from_slice(bytes);
+    Ok(sig)
+  }
+
+  /// Converts the signature into its heap allocated bytes without copying
+  pub fn into_vec(self) -> Vec<u8>
+  {
+    self.bytes.into_vec()
+  }
+
+  fn zeroed() -> Self
+  {
+    Self { bytes: vec![0u8; P::BYTES].into_boxed_slice(), params: PhantomData }
+  }
+
+  fn bytes_mut(&mut self) -> &mut [u8]
+  {
+    &mut self.bytes
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> Clone for Signature<P> {
+  fn clone(&self) -> Self {
+    Self { bytes: self.bytes.clone(), params: PhantomData }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> AsRef<[u8]> for Signature<P> {
+  fn as_ref(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> PartialEq for Signature<P> {
+  fn eq(&self, other: &Self) -> bool {
+    self.as_ref() == other.as_ref()
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> Eq for Signature<P> {}
+
+#[cfg(feature = "alloc")]
+impl<P: ParameterSet> fmt::Debug for Signature<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Signature").field(&self.as_ref()).finish()
+  }
+}
@@ -1,12 +1,12 @@
-/// Constant time implementation of the Haraka hash function.
-///
-/// The bit-sliced implementation of the AES round functions are
-/// based on the AES implementation in BearSSL written 
-/// by Thomas Pornin <pornin@bolet.org>
+//! Constant time implementation of the Haraka hash function.
+//!
+//! The bit-sliced implementation of the AES round functions are
+//! based on the AES implementation in BearSSL written 
+//! by Thomas Pornin <pornin@bolet.org>
 
 use core::ops::{BitXor, BitAnd, Not};
 use crate::context::SpxCtx;
-use crate::params::SPX_N;
+use crate::params::ParameterSet;
 
 const HARAKAS_RATE: usize = 32;
 
@@ -46,7 +46,7 @@ fn br_range_dec32le(v: &mut [u32], mut num: usize, src: &[u8])
 fn _br_swap32(mut x: u32) -> u32
 {
   x = ((x & 0x00FF00FFu32 ) << 8) | ((x >> 8) & 0x00FF00FFu32);
-  (x << 16) | (x >> 16)
+  x.rotate_left(16)
 }
 
 fn  br_enc32le(dst: &mut [u8], x: u32) 
@@ -281,7 +281,6 @@ fn br_aes_ct_ortho(q: &mut [u32])
   swap8_32!(3, 7);
 }
 
-#[cfg(feature = "robust")]
 pub fn add_round_key32(q: &mut[u32], sk: &[u32]) 
 {
   q[0] ^= sk[0];
@@ -294,7 +293,6 @@ pub fn add_round_key32(q: &mut[u32], sk: &[u32])
   q[7] ^= sk[7];
 }
 
-#[cfg(feature = "robust")]
 pub fn shift_rows32(q: &mut[u32])
 {
   for x in q.iter_mut() {
@@ -305,13 +303,11 @@ pub fn shift_rows32(q: &mut[u32])
   }
 }
 
-#[cfg(feature = "robust")]
 fn rotr16(x: u32) -> u32
 {
-  (x << 16) | (x >> 16)
+  x.rotate_right(16)
 }
 
-#[cfg(feature = "robust")]
 pub fn mix_columns32(q: &mut[u32])
 {
   let q0 = q[0];
@@ -322,14 +318,14 @@ pub fn mix_columns32(q: &mut[u32])
   let q5 = q[5];
   let q6 = q[6];
   let q7 = q[7];
-  let r0 = (q0 >> 8) | (q0 << 24);
-  let r1 = (q1 >> 8) | (q1 << 24);
-  let r2 = (q2 >> 8) | (q2 << 24);
-  let r3 = (q3 >> 8) | (q3 << 24);
-  let r4 = (q4 >> 8) | (q4 << 24);
-  let r5 = (q5 >> 8) | (q5 << 24);
-  let r6 = (q6 >> 8) | (q6 << 24);
-  let r7 = (q7 >> 8) | (q7 << 24);
+  let r0 = q0.rotate_right(8);
+  let r1 = q1.rotate_right(8);
+  let r2 = q2.rotate_right(8);
+  let r3 = q3.rotate_right(8);
+  let r4 = q4.rotate_right(8);
+  let r5 = q5.rotate_right(8);
+  let r6 = q6.rotate_right(8);
+  let r7 = q7.rotate_right(8);
 
   q[0] = q7 ^ r7 ^ r0 ^ rotr16(q0 ^ r0);
   q[1] = q0 ^ r0 ^ q7 ^ r7 ^ r1 ^ rotr16(q1 ^ r1);
@@ -465,7 +461,7 @@ pub fn shift_rows(q: &mut[u64])
 
 fn rotr32(x: u64) -> u64 
 {
-  (x << 32) | (x >> 32)
+  x.rotate_right(32)
 }
 
 fn mix_columns(q: &mut [u64])
@@ -478,14 +474,14 @@ fn mix_columns(q: &mut [u64])
   let q5 = q[5];
   let q6 = q[6];
   let q7 = q[7];
-  let r0 = (q0 >> 16) | (q0 << 48);
-  let r1 = (q1 >> 16) | (q1 << 48);
-  let r2 = (q2 >> 16) | (q2 << 48);
-  let r3 = (q3 >> 16) | (q3 << 48);
-  let r4 = (q4 >> 16) | (q4 << 48);
-  let r5 = (q5 >> 16) | (q5 << 48);
-  let r6 = (q6 >> 16) | (q6 << 48);
-  let r7 = (q7 >> 16) | (q7 << 48);
+  let r0 = q0.rotate_right(16);
+  let r1 = q1.rotate_right(16);
+  let r2 = q2.rotate_right(16);
+  let r3 = q3.rotate_right(16);
+  let r4 = q4.rotate_right(16);
+  let r5 = q5.rotate_right(16);
+  let r6 = q6.rotate_right(16);
+  let r7 = q7.rotate_right(16);
 
   q[0] = q7 ^ r7 ^ r0 ^ rotr32(q0 ^ r0);
   q[1] = q0 ^ r0 ^ q7 ^ r7 ^ r1 ^ rotr32(q1 ^ r1);
@@ -504,7 +500,7 @@ pub fn interleave_constant(out: &mut[u64], input: &[u8])
   br_range_dec32le(&mut tmp_32_constant, 16, input);
   for i in 0..4  {
     let mut q1_tmp =  out[i + 4];
-      br_aes_ct64_interleave_in(&mut out[i], &mut q1_tmp, &mut tmp_32_constant[(i << 2)..]);
+      br_aes_ct64_interleave_in(&mut out[i], &mut q1_tmp, &tmp_32_constant[(i << 2)..]);
       out[i + 4] = q1_tmp;
     }
   br_aes_ct64_ortho(out);
@@ -519,7 +515,7 @@ pub fn interleave_constant32(out: &mut[u32], input: &[u8])
   br_aes_ct_ortho(out);
 }
 
-pub fn tweak_constants(ctx: &mut SpxCtx)
+pub fn tweak_constants<P: ParameterSet>(ctx: &mut SpxCtx)
 {
   let mut buf = [0u8; 40*16];
 
@@ -527,7 +523,7 @@ pub fn tweak_constants(ctx: &mut SpxCtx)
   ctx.tweaked512_rc64 = HARAKA512_RC64;
 
   // Constants for pk.seed
-  haraka_s(&mut buf, 40*16, &ctx.pub_seed, SPX_N, &ctx);
+  haraka_s(&mut buf, 40*16, &ctx.pub_seed, P::N, ctx);
   for i in 0..10  {
     interleave_constant32(&mut ctx.tweaked256_rc32[i], &buf[32*i..]);
     interleave_constant(&mut ctx.tweaked512_rc64[i], &buf[64*i..]);
@@ -550,9 +546,7 @@ pub fn haraka_s_absorb(
     idx += HARAKAS_RATE;
   }
   
-  for i in 0..mlen  {
-    t[i] = m[idx + i];
-  }
+  t[..mlen].copy_from_slice(&m[idx..idx + mlen]);
 
   t[mlen] = p;
   t[HARAKAS_RATE - 1] |= 128;
@@ -649,11 +643,10 @@ pub fn haraka_s(
   haraka_s_squeezeblocks(out, outlen / 32, &mut s, 32, ctx);
   idx += (outlen / 32) * 32;
 
-  if outlen % 32 != 0 {
+  let rem = outlen % 32;
+  if rem != 0 {
     haraka_s_squeezeblocks(&mut d, 1, &mut s, 32, ctx);
-    for i in 0..outlen % 32 {
-      out[idx + i] = d[i];
-    }
+    out[idx..idx + rem].copy_from_slice(&d[..rem]);
   }
 }
 
@@ -663,7 +656,7 @@ pub fn haraka512_perm(out: &mut[u8], ctx: &SpxCtx)
   let mut w = [0u32; 16];
   let mut q = [0u64; 8];
 
-  br_range_dec32le(&mut w, 16, &out);
+  br_range_dec32le(&mut w, 16, out);
   for i in 0..4  {
     tmp_q = q[i + 4];
     br_aes_ct64_interleave_in(&mut q[i], &mut tmp_q, &w[(i << 2)..]);
@@ -680,9 +673,9 @@ pub fn haraka512_perm(out: &mut[u8], ctx: &SpxCtx)
       add_round_key(&mut q, &ctx.tweaked512_rc64[2*i + j]);
     }
     // Mix states
-    for j in 0..8  {
-      tmp_q = q[j];
-      q[j] =  (tmp_q & 0x0001000100010001) << 5  |
+    for qj in q.iter_mut()  {
+      tmp_q = *qj;
+      *qj =  (tmp_q & 0x0001000100010001) << 5  |
               (tmp_q & 0x0002000200020002) << 12 | 
               (tmp_q & 0x0004000400040004) >> 1  | 
               (tmp_q & 0x0008000800080008) << 6  | 
@@ -708,7 +701,7 @@ pub fn haraka512_perm(out: &mut[u8], ctx: &SpxCtx)
 pub fn haraka512(out: &mut[u8], input: &[u8], ctx: &SpxCtx)
 {
   let mut buf = [0u8;64];
-  buf.clone_from_slice(&input);
+  buf.clone_from_slice(input);
 
   haraka512_perm(&mut buf, ctx);
   // Feed-forward
@@ -723,7 +716,6 @@ pub fn haraka512(out: &mut[u8], input: &[u8], ctx: &SpxCtx)
   out[24..32].copy_from_slice(&buf[48..56]);
 }
 
-#[cfg(feature = "robust")]
 pub fn haraka256(out: &mut[u8], input: &[u8], ctx: &SpxCtx)
 {
   let mut q = [0u32; 8];
@@ -744,9 +736,9 @@ pub fn haraka256(out: &mut[u8], input: &[u8], ctx: &SpxCtx)
     }
 
     // Mix states
-    for j in 0..8  {
-      tmp_q = q[j];
-      q[j] =  (tmp_q & 0x81818181) |
+    for qj in q.iter_mut()  {
+      tmp_q = *qj;
+      *qj =  (tmp_q & 0x81818181) |
               (tmp_q & 0x02020202) << 1 |
               (tmp_q & 0x04040404) << 2 |
               (tmp_q & 0x08080808) << 3 |
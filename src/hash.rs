@@ -1,17 +1,103 @@
-#[cfg(feature = "haraka")]
 mod haraka; 
-
-#[cfg(feature = "sha2")]
 mod sha2; 
-
-#[cfg(feature = "shake")]
 mod shake;
 
-#[cfg(feature = "haraka")]
-pub use haraka::*; 
+use crate::context::SpxCtx;
+use crate::params::{HashFamily, ParameterSet};
+
+/// This hook allows the hash function instantiation to do whatever
+/// preparation or computation it needs, based on the public seed.
+pub fn initialize_hash_function<P: ParameterSet>(ctx: &mut SpxCtx)
+{
+  match P::HASH {
+    HashFamily::Haraka => haraka::initialize_hash_function::<P>(ctx),
+    HashFamily::Sha2 => sha2::initialize_hash_function::<P>(ctx),
+    HashFamily::Shake => shake::initialize_hash_function(ctx)
+  }
+}
+
+/// Computes PRF(pk_seed, sk_seed, addr)
+pub fn prf_addr<P: ParameterSet>(out: &mut[u8], ctx: &SpxCtx, addr: &mut[u32])
+{
+  match P::HASH {
+    HashFamily::Haraka => haraka::prf_addr::<P>(out, ctx, addr),
+    HashFamily::Sha2 => sha2::prf_addr::<P>(out, ctx, addr),
+    HashFamily::Shake => shake::prf_addr::<P>(out, ctx, addr)
+  }
+}
+
+/// Incremental computation of the message-dependent randomness R, see the 
+/// PrfMsg of each hash function
+pub enum PrfMsg {
+  Haraka(haraka::PrfMsg),
+  Sha2(sha2::PrfMsg),
+  Shake(shake::PrfMsg)
+}
+
+impl PrfMsg {
+  pub fn new<P: ParameterSet>(sk_prf: &[u8], optrand: &[u8], ctx: &SpxCtx) -> Self
+  {
+    match P::HASH {
+      HashFamily::Haraka => Self::Haraka(haraka::PrfMsg::new::<P>(sk_prf, optrand, ctx)),
+      HashFamily::Sha2 => Self::Sha2(sha2::PrfMsg::new::<P>(sk_prf, optrand, ctx)),
+      HashFamily::Shake => Self::Shake(shake::PrfMsg::new::<P>(sk_prf, optrand, ctx))
+    }
+  }
+
+  pub fn update<P: ParameterSet>(&mut self, m: &[u8], ctx: &SpxCtx)
+  {
+    match self {
+      Self::Haraka(prf) => prf.update(m, ctx),
+      Self::Sha2(prf) => prf.update::<P>(m, ctx),
+      Self::Shake(prf) => prf.update(m, ctx)
+    }
+  }
+
+  pub fn finalize<P: ParameterSet>(self, r: &mut[u8], ctx: &SpxCtx)
+  {
+    match self {
+      Self::Haraka(prf) => prf.finalize::<P>(r, ctx),
+      Self::Sha2(prf) => prf.finalize::<P>(r, ctx),
+      Self::Shake(prf) => prf.finalize::<P>(r, ctx)
+    }
+  }
+}
+
+/// Incremental computation of the message digest and leaf index, see the 
+/// HashMsg of each hash function
+pub enum HashMsg {
+  Haraka(haraka::HashMsg),
+  Sha2(sha2::HashMsg),
+  Shake(shake::HashMsg)
+}
+
+impl HashMsg {
+  pub fn new<P: ParameterSet>(r: &[u8], pk: &[u8], ctx: &SpxCtx) -> Self
+  {
+    match P::HASH {
+      HashFamily::Haraka => Self::Haraka(haraka::HashMsg::new::<P>(r, pk, ctx)),
+      HashFamily::Sha2 => Self::Sha2(sha2::HashMsg::new::<P>(r, pk, ctx)),
+      HashFamily::Shake => Self::Shake(shake::HashMsg::new::<P>(r, pk, ctx))
+    }
+  }
 
-#[cfg(feature = "sha2")]
-pub use sha2::*; 
+  pub fn update<P: ParameterSet>(&mut self, m: &[u8], ctx: &SpxCtx)
+  {
+    match self {
+      Self::Haraka(hmsg) => hmsg.update(m, ctx),
+      Self::Sha2(hmsg) => hmsg.update::<P>(m, ctx),
+      Self::Shake(hmsg) => hmsg.update(m, ctx)
+    }
+  }
 
-#[cfg(feature = "shake")]
-pub use shake::*; 
+  pub fn finalize<P: ParameterSet>(
+    self, digest: &mut[u8], tree: &mut u64, leaf_idx: &mut u32, ctx: &SpxCtx
+  )
+  {
+    match self {
+      Self::Haraka(hmsg) => hmsg.finalize::<P>(digest, tree, leaf_idx, ctx),
+      Self::Sha2(hmsg) => hmsg.finalize::<P>(digest, tree, leaf_idx, ctx),
+      Self::Shake(hmsg) => hmsg.finalize::<P>(digest, tree, leaf_idx, ctx)
+    }
+  }
+}
@@ -5,64 +5,87 @@ use sha3::{Shake256, digest::{Update, ExtendableOutput, XofReader}};
 
 /// For SHAKE256, there is no immediate reason to initialize at the start,
 /// so this function is an empty operation.
-pub fn initialize_hash_function(_ctx: &mut SpxCtx) { () }
+pub fn initialize_hash_function(_ctx: &mut SpxCtx) {}
 
 ///Computes PRF(pk_seed, sk_seed, addr)
-pub fn prf_addr(out: &mut[u8], ctx: &SpxCtx, addr: &mut[u32])
+pub fn prf_addr<P: ParameterSet>(out: &mut[u8], ctx: &SpxCtx, addr: &mut[u32])
 {
-  let mut idx = SPX_N; 
-  let mut buf = [0u8; 2*SPX_N + SPX_ADDR_BYTES];
-  buf[..SPX_N].copy_from_slice(&ctx.pub_seed);
-  buf[SPX_N..idx + SPX_ADDR_BYTES].copy_from_slice(&address_to_bytes(addr));
-  idx += SPX_ADDR_BYTES;
-  buf[idx..idx + SPX_N].copy_from_slice(&ctx.sk_seed);
-
   let mut hasher = Shake256::default();
-  hasher.update(&buf[..2*SPX_N + SPX_ADDR_BYTES]); 
+  hasher.update(&ctx.pub_seed[..P::N]);
+  hasher.update(&address_to_bytes(addr));
+  hasher.update(&ctx.sk_seed[..P::N]);
   let mut reader = hasher.finalize_xof();
-  reader.read(&mut out[..SPX_N]);
+  reader.read(&mut out[..P::N]);
 }
 
-/// Computes the message-dependent randomness R, using a secret seed and an
-/// optional randomization value as well as the message.
-pub fn gen_message_random(
-  r: &mut[u8], sk_prf: &[u8], optrand: &[u8], 
-  m: &[u8], mlen: usize, _ctx: &SpxCtx
-)
-{
-  let mut hasher = Shake256::default();
-  hasher.update(&sk_prf[..SPX_N]); 
-  hasher.update(&optrand[..SPX_N]);
-  hasher.update(&m[..mlen as usize]);
-  let mut reader = hasher.finalize_xof();
-  reader.read(&mut r[..SPX_N]);
+/// Incremental computation of the message-dependent randomness R, using a 
+/// secret seed and an optional randomization value as well as the message.
+pub struct PrfMsg {
+  hasher: Shake256
 }
 
-/// Computes the message hash using R, the public key, and the message.
-/// Outputs the message digest and the index of the leaf. The index is split in
-/// the tree index and the leaf index, for convenient copying to an address.
-pub fn hash_message(
-  digest: &mut[u8], tree: &mut u64, leaf_idx: &mut u32, 
-  r: &[u8], pk: &[u8], m: &[u8], mlen: usize, _ctx: &SpxCtx
-)
-{
-  let mut buf = [0u8; SPX_DGST_BYTES];
-  let mut idx  = 0;
+impl PrfMsg {
+  pub fn new<P: ParameterSet>(sk_prf: &[u8], optrand: &[u8], _ctx: &SpxCtx) -> Self
+  {
+    let mut hasher = Shake256::default();
+    hasher.update(&sk_prf[..P::N]); 
+    hasher.update(&optrand[..P::N]);
+    Self { hasher }
+  }
 
-  let mut hasher = Shake256::default();
-  hasher.update(&r[..SPX_N]); 
-  hasher.update(&pk[..SPX_PK_BYTES]);
-  hasher.update(&m[..mlen as usize]);
-  let mut reader = hasher.finalize_xof();
-  reader.read(&mut buf[..SPX_DGST_BYTES]);
+  pub fn update(&mut self, m: &[u8], _ctx: &SpxCtx)
+  {
+    self.hasher.update(m);
+  }
+
+  pub fn finalize<P: ParameterSet>(self, r: &mut[u8], _ctx: &SpxCtx)
+  {
+    let mut reader = self.hasher.finalize_xof();
+    reader.read(&mut r[..P::N]);
+  }
+}
+
+/// Incremental computation of the message hash using R, the public key, and 
+/// the message. Outputs the message digest and the index of the leaf. The 
+/// index is split in the tree index and the leaf index, for convenient 
+/// copying to an address.
+pub struct HashMsg {
+  hasher: Shake256
+}
+
+impl HashMsg {
+  pub fn new<P: ParameterSet>(r: &[u8], pk: &[u8], _ctx: &SpxCtx) -> Self
+  {
+    let mut hasher = Shake256::default();
+    hasher.update(&r[..P::N]); 
+    hasher.update(&pk[..P::PK_BYTES]);
+    Self { hasher }
+  }
+
+  pub fn update(&mut self, m: &[u8], _ctx: &SpxCtx)
+  {
+    self.hasher.update(m);
+  }
+
+  pub fn finalize<P: ParameterSet>(
+    self, digest: &mut[u8], tree: &mut u64, leaf_idx: &mut u32, _ctx: &SpxCtx
+  )
+  {
+    let mut buf = [0u8; SPX_MAX_DGST_BYTES];
+    let mut idx  = 0;
+
+    let mut reader = self.hasher.finalize_xof();
+    reader.read(&mut buf[..P::DGST_BYTES]);
 
-  digest[..SPX_FORS_MSG_BYTES].copy_from_slice(&buf[..SPX_FORS_MSG_BYTES]);
-  idx += SPX_FORS_MSG_BYTES;
+    digest[..P::FORS_MSG_BYTES].copy_from_slice(&buf[..P::FORS_MSG_BYTES]);
+    idx += P::FORS_MSG_BYTES;
 
-  *tree = bytes_to_ull(&buf[idx..], SPX_TREE_BYTES);
-  *tree &= !0 >> (64 - SPX_TREE_BITS);
-  idx += SPX_TREE_BYTES;
+    *tree = bytes_to_ull(&buf[idx..], P::TREE_BYTES);
+    // A single layer (d = 1) has no tree index bits
+    *tree &= (!0u64).checked_shr((64 - P::TREE_BITS) as u32).unwrap_or(0);
+    idx += P::TREE_BYTES;
 
-  *leaf_idx = bytes_to_ull(&buf[idx..], SPX_LEAF_BYTES) as u32;
-  *leaf_idx &= !0 >> (32 - SPX_LEAF_BITS);
+    *leaf_idx = bytes_to_ull(&buf[idx..], P::LEAF_BYTES) as u32;
+    *leaf_idx &= !0 >> (32 - P::LEAF_BITS);
+  }
 }
@@ -17,20 +17,108 @@
 //! 
 //! ```toml
 //! [dependencies]
-//! pqc_sphincsplus = {version = "0.1.0", features = ["haraka", "f128", "simple"]}
+//! pqc_sphincsplus = {version = "0.3.0", features = ["haraka", "f128", "simple", "std"]}
 //! ```
 //! 
 //! To generate a keypair and sign a message with it:
 //! 
 //! ```no_run
 //!  use pqc_sphincsplus::*;
-//!  let keys = keypair();
+//!  let keys = keypair().unwrap();
 //!  let msg = [0u8; 32];
-//!  let sig = sign(&msg, &keys);
-//!  let sig_verify = verify(&sig, &msg, &keys);
+//!  let sig = sign(&msg, &keys).unwrap();
+//!  let sig_verify = verify(sig.as_ref(), &msg, &keys.public);
 //!  assert!(sig_verify.is_ok());
 //! ```
 //! 
+//! Verifiers that only hold a public key can use a [`VerifyingKey`]:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  # let keys = keypair().unwrap();
+//!  # let msg = [0u8; 32];
+//!  # let sig = sign(&msg, &keys).unwrap();
+//!  let vk = VerifyingKey::from_bytes(keys.public.as_ref()).unwrap();
+//!  assert!(vk.verify(sig.as_ref(), &msg).is_ok());
+//! ```
+//! 
+//! Attached signatures in the NIST `sm = sig || msg` format need the `alloc` 
+//! feature, which `std` enables:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  # let keys = keypair().unwrap();
+//!  let sm = sign_attached(b"hello", &keys).unwrap();
+//!  assert_eq!(open(&sm, &keys.public).unwrap(), b"hello");
+//! ```
+//! 
+//! Signatures are up to 49,856 bytes. With `alloc` a [`Signature`] lives on 
+//! the heap, on small or embedded stacks [`sign_into`] writes the signature 
+//! straight into a caller provided buffer instead:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  # let keys = keypair().unwrap();
+//!  # let msg = [0u8; 32];
+//!  let mut sig = [0u8; CRYPTO_BYTES];
+//!  sign_into(&mut sig, &msg, &keys).unwrap();
+//! ```
+//! 
+//! Large files can be signed and verified without loading them into memory 
+//! using [`sign_reader`] and [`verify_reader`], this needs `std`:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  # let keys = keypair().unwrap();
+//!  let mut file = std::fs::File::open("artifact.bin").unwrap();
+//!  let sig = sign_reader(&mut file, &keys).unwrap();
+//! ```
+//! 
+//! Data arriving in chunks, for example over a socket, is verified with a 
+//! [`Verifier`]:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  # let keys = keypair().unwrap();
+//!  # let sig = sign(b"chunked", &keys).unwrap();
+//!  let mut verifier = VerifyingKey::from(&keys).verifier(sig.as_ref()).unwrap();
+//!  verifier.update(b"chun");
+//!  verifier.update(b"ked");
+//!  assert!(verifier.finalize().is_ok());
+//! ```
+//! 
+//! The features only choose the parameter set used by the functions above. 
+//! Every parameter set is compiled in and can be chosen at runtime with 
+//! [`ParameterSetId`], working on plain byte buffers:
+//! 
+//! ```no_run
+//!  # use pqc_sphincsplus::*;
+//!  let params = ParameterSetId::from_name("sphincs-shake-256s-simple").unwrap();
+//!  let mut pk = vec![0u8; params.public_key_bytes()];
+//!  let mut sk = vec![0u8; params.secret_key_bytes()];
+//!  params.keypair(&mut pk, &mut sk).unwrap();
+//!  let mut sig = vec![0u8; params.signature_bytes()];
+//!  params.sign_into(&mut sig, b"hello", &sk).unwrap();
+//!  assert!(params.verify(&sig, b"hello", &pk).is_ok());
+//! ```
+//! 
+//! When the parameter set is known at compile time the [`generic`] module 
+//! types keys and signatures by it, so keys of different parameter sets 
+//! cannot be mixed up:
+//! 
+//! ```no_run
+//!  use pqc_sphincsplus::generic::{SigningKey, Sha2_128sSimple};
+//!  let sk = SigningKey::<Sha2_128sSimple>::generate().unwrap();
+//!  let sig = sk.sign(b"hello").unwrap();
+//!  assert!(sk.verifying_key().verify(&sig, b"hello").is_ok());
+//! ```
+//! 
+//! Other choices of n, h, d, log t, k and w can be defined with 
+//! [`custom_parameter_set`], which checks them at compile time.
+//! 
+//! The reduced-use SLH-DSA parameter sets are not provided until NIST 
+//! publishes their values and test vectors.
+//! 
 //! The security levels target 128, 192 and 256 bit equivalents, corresponding to NIST
 //! levels 1,3,5 respectively. They are also separated into **fast** (f) and **small** 
 //! (s) subtypes, which make the tradeoff between either quicker signing or smaller 
@@ -59,6 +147,43 @@
 //!   * `simple`
 //!   * `robust`
 //! 
+//! * ### Randomness
+//!   * `std` - use rand's `thread_rng` for key generation and signing
+//!   * `getrandom` - use the operating system RNG through `getrandom`
+//!   * `alloc` - heap allocated signatures and attached signatures, implied 
+//!     by `std`
+//! 
+//! These are all off by default and the library builds for bare metal targets 
+//! without them. In that case only [`keypair_with_rng`], [`sign_with_rng`] 
+//! and friends are available and the caller supplies the randomness with 
+//! any RNG implementing `RngCore + CryptoRng`.
+//! 
+//! * ### FIPS 205
+//!   * `slh-dsa` - SLH-DSA signatures under a context string, see 
+//!     [`slh_dsa_sign`]. They interoperate with other FIPS 205 
+//!     implementations for the sha2 and shake simple parameter sets. The 
+//!     default api keeps producing SPHINCS+ 3.1 signatures, the two are not 
+//!     valid for each other.
+//! 
+//! * ### Pre-hash
+//!   * `prehash` - HashSLH-DSA signatures over a SHA-2, SHA-3, SHAKE128 
+//!     or SHAKE256 digest of the message, see [`sign_prehash`]. 
+//!     The digest algorithm OID and a context string are signed along with 
+//!     the digest so pre-hash and pure signatures never collide. Available 
+//!     with every hash choice and encoded as in FIPS 205.
+//! 
+//! * ### Self tests
+//!   * `pct` - run a pairwise consistency test on every generated keypair, 
+//!     a failing keypair is zeroed and [`SigError::PairwiseConsistency`] 
+//!     returned instead. Implies `alloc`, the test signature is heap 
+//!     allocated
+//!   * `hardened` - compute every subtree below the top layer twice and 
+//!     compare the roots before signing them, recompute every hypertree 
+//!     layer from its WOTS signature and verify each signature before it is 
+//!     released. A faulty signature is zeroed and [`SigError::FaultDetected`] 
+//!     returned instead. The redundant subtrees roughly double the hypertree 
+//!     part of signing.
+//! 
 //! A comparison of the different security levels is below.
 //! 
 //! 
@@ -72,8 +197,6 @@
 //! | SPHINCS+-256f | 32 | 68 | 17 |      9 | 35 |  16 |          255 |       64 |      128 |    49,856 |
 //! 
 #![no_std]
-#![allow(incomplete_features)]
-#![feature(generic_const_exprs)]
 
 // Require one from each category 
 #![cfg(all(
@@ -95,9 +218,19 @@ macro_rules! assert_unique_feature {
   }
 }
 assert_unique_feature!("haraka", "shake", "sha2");
-assert_unique_feature!("f128", "f192", "f256","s128", "s192", "s256");
+assert_unique_feature!(
+  "f128", "f192", "f256","s128", "s192", "s256"
+);
 assert_unique_feature!("robust", "simple");
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+pub mod generic;
+
 mod api;
 mod address;
 mod context;
@@ -113,14 +246,24 @@ mod utilsx1;
 mod wots;
 mod wotsx1;
 mod randombytes;
+mod runtime;
+mod sha2;
+mod haraka;
 
 pub use api::*;
+pub use runtime::ParameterSetId;
 
-#[cfg(feature = "sha2")] 
-mod sha2;
+// Used by the custom_parameter_set macro
+#[doc(hidden)]
+pub mod __private {
+  pub use crate::params::{private::Sealed, validate};
+}
 
-#[cfg(feature = "haraka")] 
-mod haraka;
+#[cfg(feature = "prehash")]
+mod prehash;
+
+#[cfg(feature = "prehash")]
+pub use prehash::PreHash;
 
 // Known Answer Tests
 #[cfg(feature = "KAT")]
@@ -5,51 +5,57 @@ use crate::wotsx1::*;
 use crate::address::*;
 use crate::params::*;
 
-const STACK_LEN: usize = SPX_TREE_HEIGHT * SPX_N;
-
 /// This generates a Merkle signature (WOTS signature followed by the Merkle
 /// authentication path).  This is in this file because most of the complexity
 /// is involved with the WOTS signature; the Merkle authentication path logic
 /// is mostly hidden in treehashx4
-pub fn merkle_sign(sig: &mut[u8], root: &mut[u8],
+pub fn merkle_sign<P: ParameterSet>(sig: &mut[u8], root: &mut[u8],
                  ctx: &SpxCtx,
                  wots_addr: &mut[u32], tree_addr: &mut[u32],
                  idx_leaf: u32)
 {
   let mut info = LeafInfoX1::default();
-  let mut steps = [0u32; SPX_WOTS_LEN];
-  chain_lengths(&mut steps, root);
-  info.wots_steps = steps;
+  chain_lengths::<P>(&mut info.wots_steps, root);
 
-  set_type(tree_addr, SPX_ADDR_TYPE_HASHTREE);
-  set_type(&mut info.pk_addr, SPX_ADDR_TYPE_WOTSPK);
-  copy_subtree_addr(&mut info.leaf_addr, wots_addr);
-  copy_subtree_addr(&mut info.pk_addr, wots_addr);
+  set_type::<P>(tree_addr, SPX_ADDR_TYPE_HASHTREE);
+  set_type::<P>(&mut info.pk_addr, SPX_ADDR_TYPE_WOTSPK);
+  copy_subtree_addr::<P>(&mut info.leaf_addr, wots_addr);
+  copy_subtree_addr::<P>(&mut info.pk_addr, wots_addr);
 
   info.wots_sign_leaf = idx_leaf;
 
-  wots_treehashx1::<SPX_TREE_HEIGHT, STACK_LEN>(
-    root, &mut sig[SPX_WOTS_BYTES..], ctx, idx_leaf, 0,tree_addr, &mut info
+  wots_treehashx1::<P>(
+    root, &mut sig[P::WOTS_BYTES..], ctx, idx_leaf, 0, tree_addr, &mut info
   );
-  sig[..SPX_WOTS_BYTES].clone_from_slice(&info.wots_sig[..SPX_WOTS_BYTES]);
+  sig[..P::WOTS_BYTES].clone_from_slice(&info.wots_sig[..P::WOTS_BYTES]);
 }
 
 /// Compute root node of the top-most subtree.
-pub fn merkle_gen_root(root: &mut[u8], ctx: &SpxCtx)
+pub fn merkle_gen_root<P: ParameterSet>(root: &mut[u8], ctx: &SpxCtx)
+{
+  merkle_subtree_root::<P>(root, ctx, P::D as u32 - 1, 0);
+}
+
+/// Compute root node of the subtree at the given layer and tree address,
+/// without signing a leaf.
+pub fn merkle_subtree_root<P: ParameterSet>(
+  root: &mut[u8], ctx: &SpxCtx, layer: u32, tree: u64
+)
 {
   // We do not need the auth path in key generation, but it simplifies the
   // code to have just one treehash routine that computes both root and path
   // in one function.
-  let mut auth_path = [0u8; SPX_TREE_HEIGHT * SPX_N + SPX_WOTS_BYTES];
-  let mut top_tree_addr = [0u32; 8];
+  let mut auth_path = [0u8; SPX_MAX_TREE_HEIGHT * SPX_MAX_N + SPX_MAX_WOTS_BYTES];
+  let mut tree_addr = [0u32; 8];
   let mut wots_addr = [0u32; 8];
 
-  set_layer_addr(&mut top_tree_addr, SPX_D as u32 - 1);
-  set_layer_addr(&mut wots_addr, SPX_D as u32 - 1);
+  set_layer_addr::<P>(&mut tree_addr, layer);
+  set_tree_addr::<P>(&mut tree_addr, tree);
+  copy_subtree_addr::<P>(&mut wots_addr, &mut tree_addr);
 
-  merkle_sign(
+  merkle_sign::<P>(
     &mut auth_path, root, ctx,
-    &mut wots_addr, &mut top_tree_addr, 
+    &mut wots_addr, &mut tree_addr, 
     !0 // ~0 means "don't bother generating an auth path
   ); 
 }
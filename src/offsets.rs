@@ -1,17 +1,16 @@
-#[cfg(feature = "haraka")]
-mod haraka; 
+pub mod haraka; 
+pub mod sha2; 
+pub mod shake;
 
-#[cfg(feature = "sha2")]
-mod sha2; 
-
-#[cfg(feature = "shake")]
-mod shake;
-
-#[cfg(feature = "haraka")]
-pub use haraka::*; 
-
-#[cfg(feature = "sha2")]
-pub use sha2::*; 
-
-#[cfg(feature = "shake")]
-pub use shake::*; 
\ No newline at end of file
+/// Offset of an address field for the hash function of a parameter set,
+/// usage: `offset!(P, SPX_OFFSET_LAYER)`
+macro_rules! offset {
+  ($p:ty, $field:ident) => {
+    match <$p as crate::params::ParameterSet>::HASH {
+      crate::params::HashFamily::Haraka => crate::offsets::haraka::$field,
+      crate::params::HashFamily::Sha2 => crate::offsets::sha2::$field,
+      crate::params::HashFamily::Shake => crate::offsets::shake::$field,
+    }
+  }
+}
+pub(crate) use offset;
@@ -1,5 +1,5 @@
-/// Offsets of various fields in the address structure when we use SHA2 as
-/// the Sphincs+ hash function
+//! Offsets of various fields in the address structure when we use SHA2 as
+//! the Sphincs+ hash function
 
 pub const SPX_OFFSET_LAYER: usize = 0;   //The byte used to specify the Merkle tree layer
 pub const SPX_OFFSET_TREE: usize = 1;   //The start of the 8 byte field used to specify the tree
@@ -1,10 +1,9 @@
-
-#[cfg(feature = "f128")] mod f128;
-#[cfg(feature = "f192")] mod f192;
-#[cfg(feature = "f256")] mod f256;
-#[cfg(feature = "s128")] mod s128;
-#[cfg(feature = "s192")] mod s192;
-#[cfg(feature = "s256")] mod s256;
+mod f128;
+mod f192;
+mod f256;
+mod s128;
+mod s192;
+mod s256;
 
 #[cfg(feature = "f128")] pub use f128::*;
 #[cfg(feature = "f192")] pub use f192::*;
@@ -13,79 +12,329 @@
 #[cfg(feature = "s192")] pub use s192::*;
 #[cfg(feature = "s256")] pub use s256::*;
 
-pub const CRYPTO_SECRETKEYBYTES: usize = SPX_SK_BYTES;
-pub const CRYPTO_PUBLICKEYBYTES: usize = SPX_PK_BYTES;
-pub const CRYPTO_BYTES: usize = SPX_BYTES;
+pub const CRYPTO_SECRETKEYBYTES: usize = Selected::SK_BYTES;
+pub const CRYPTO_PUBLICKEYBYTES: usize = Selected::PK_BYTES;
+pub const CRYPTO_BYTES: usize = Selected::BYTES;
 pub const CRYPTO_SEEDBYTES: usize = 3*SPX_N;
 
-/// Winternitz parameter,
-pub const SPX_WOTS_W: usize = 16;
-
 /// For clarity
-#[cfg(any(feature = "haraka", feature = "shake"))]
 pub const SPX_ADDR_BYTES: usize = 32;
 
-/// WOTS parameters.
-pub const SPX_WOTS_LOGW: usize = if SPX_WOTS_W == 256 {
-  8 
-} else { // if SPX_WOTS_W == 16
-  4
+/// Largest values over all parameter sets, these size the stack buffers
+/// shared by every parameter set.
+pub const SPX_MAX_N: usize = 32;
+/// From n = 32 and w = 4
+pub const SPX_MAX_WOTS_LEN: usize = 133;
+pub const SPX_MAX_WOTS_BYTES: usize = SPX_MAX_WOTS_LEN * SPX_MAX_N;
+pub const SPX_MAX_TREE_HEIGHT: usize = 16;
+pub const SPX_MAX_FORS_HEIGHT: usize = 16;
+pub const SPX_MAX_FORS_TREES: usize = 35;
+pub const SPX_MAX_FORS_MSG_BYTES: usize =
+  (SPX_MAX_FORS_HEIGHT * SPX_MAX_FORS_TREES).div_ceil(8);
+pub const SPX_MAX_DGST_BYTES: usize = SPX_MAX_FORS_MSG_BYTES + 8 + 2;
+/// Longest signature, from the 256f parameter sets
+pub const SPX_MAX_BYTES: usize = 49856;
+/// Most SPX_MAX_N byte blocks a tweakable hash takes, from the WOTS public 
+/// key or the FORS roots
+pub const SPX_MAX_THASH_BLOCKS: usize = if SPX_MAX_WOTS_LEN > SPX_MAX_FORS_TREES {
+  SPX_MAX_WOTS_LEN
+} else {
+  SPX_MAX_FORS_TREES
 };
 
-pub const SPX_WOTS_LEN: usize = SPX_WOTS_LEN1 + SPX_WOTS_LEN2;
-pub const SPX_WOTS_BYTES: usize = SPX_WOTS_LEN * SPX_N;
-// pub const SPX_WOTS_PK_BYTES: usize = SPX_WOTS_BYTES;
-
-/// Subtree size.
-pub const SPX_TREE_HEIGHT: usize = SPX_FULL_HEIGHT / SPX_D;
-
-/// FORS parameters.
-pub const SPX_FORS_MSG_BYTES: usize = (SPX_FORS_HEIGHT * SPX_FORS_TREES + 7) / 8;
-pub const SPX_FORS_BYTES: usize = (SPX_FORS_HEIGHT + 1) * SPX_FORS_TREES * SPX_N;
-// pub const SPX_FORS_PK_BYTES: usize = SPX_N;
-
-/// Resulting SPX sizes.
-pub const SPX_BYTES: usize = SPX_N + SPX_FORS_BYTES + SPX_D * SPX_WOTS_BYTES + SPX_FULL_HEIGHT * SPX_N;
-pub const SPX_PK_BYTES: usize = 2 * SPX_N;
-pub const SPX_SK_BYTES: usize = 2 * SPX_N + SPX_PK_BYTES;
-
-// pub const WOTS_SIG_LEN: usize = SPX_TREE_HEIGHT * SPX_N + SPX_WOTS_BYTES;
-
-pub const SPX_TREE_BITS: usize = SPX_TREE_HEIGHT * (SPX_D - 1);
-pub const SPX_TREE_BYTES: usize = (SPX_TREE_BITS + 7) / 8;
-pub const SPX_LEAF_BITS: usize = SPX_TREE_HEIGHT;
-pub const SPX_LEAF_BYTES: usize = (SPX_LEAF_BITS + 7) / 8;
-pub const SPX_DGST_BYTES: usize = SPX_FORS_MSG_BYTES + SPX_TREE_BYTES + SPX_LEAF_BYTES;
-
-pub const SPX_WOTS_LEN1: usize = 8 * SPX_N / SPX_WOTS_LOGW;
-
-/// SPX_WOTS_LEN2 is floor(log(len_1 * (w - 1)) / log(w)) + 1; we precompute
-pub const SPX_WOTS_LEN2: usize = if SPX_WOTS_W == 256 {
-  if SPX_N <= 1 {
-    1
-  } else { // if SPX_N <= 256
-    2
-  } 
-} else { //if SPX_WOTS_W == 16
-  if SPX_N <= 8 {
-    2
-  } else if SPX_N <= 136 {
-    3
-  } else  { // if SPX_N <= 256
-    4
+/// Hash function the tweakable hash functions are built on
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HashFamily {
+  Haraka,
+  Sha2,
+  Shake
+}
+
+/// Tweakable hash construction, the robust variant masks every input with
+/// a bitmask derived from the public seed and address
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Thash {
+  Simple,
+  Robust
+}
+
+pub(crate) mod private {
+  /// Only the parameter sets defined by this crate or through 
+  /// [`custom_parameter_set`](crate::custom_parameter_set) implement 
+  /// ParameterSet
+  pub trait Sealed {}
+}
+
+/// The parameters of one SPHINCS+ instance, everything else is derived
+/// from them.
+/// 
+/// Implemented by a marker type for each parameter set, see 
+/// [`crate::generic`], and by those defined with 
+/// [`crate::custom_parameter_set`].
+pub trait ParameterSet: private::Sealed {
+  /// Hash output length in bytes, n
+  const N: usize;
+  /// Height of the hypertree, h
+  const FULL_HEIGHT: usize;
+  /// Number of subtree layers, d
+  const D: usize;
+  /// Height of the FORS trees, log(t)
+  const FORS_HEIGHT: usize;
+  /// Number of FORS trees, k
+  const FORS_TREES: usize;
+  /// Winternitz parameter, w
+  const WOTS_W: usize;
+  /// Hash function family
+  const HASH: HashFamily;
+  /// Tweakable hash construction
+  const THASH: Thash;
+
+  /// WOTS parameters, w is 4, 16 or 256.
+  const WOTS_LOGW: usize = Self::WOTS_W.trailing_zeros() as usize;
+
+  const WOTS_LEN1: usize = 8 * Self::N / Self::WOTS_LOGW;
+
+  /// WOTS_LEN2 is floor(log(len_1 * (w - 1)) / log(w)) + 1, the number of 
+  /// base w digits of the largest checksum
+  const WOTS_LEN2: usize = {
+    let mut csum = Self::WOTS_LEN1 * (Self::WOTS_W - 1);
+    let mut len2 = 0;
+    while csum > 0 {
+      csum /= Self::WOTS_W;
+      len2 += 1;
+    }
+    len2
+  };
+
+  const WOTS_LEN: usize = Self::WOTS_LEN1 + Self::WOTS_LEN2;
+  const WOTS_BYTES: usize = Self::WOTS_LEN * Self::N;
+
+  /// Subtree size.
+  const TREE_HEIGHT: usize = Self::FULL_HEIGHT / Self::D;
+
+  /// FORS parameters.
+  const FORS_MSG_BYTES: usize = (Self::FORS_HEIGHT * Self::FORS_TREES).div_ceil(8);
+  const FORS_BYTES: usize = (Self::FORS_HEIGHT + 1) * Self::FORS_TREES * Self::N;
+
+  /// Resulting SPX sizes, the signature
+  const BYTES: usize = Self::N + Self::FORS_BYTES + Self::D * Self::WOTS_BYTES
+    + Self::FULL_HEIGHT * Self::N;
+  /// Public key, [PUB_SEED || root]
+  const PK_BYTES: usize = 2 * Self::N;
+  /// Secret key, [SK_SEED || SK_PRF || PUB_SEED || root]
+  const SK_BYTES: usize = 2 * Self::N + Self::PK_BYTES;
+
+  const TREE_BITS: usize = Self::TREE_HEIGHT * (Self::D - 1);
+  const TREE_BYTES: usize = Self::TREE_BITS.div_ceil(8);
+  const LEAF_BITS: usize = Self::TREE_HEIGHT;
+  const LEAF_BYTES: usize = Self::LEAF_BITS.div_ceil(8);
+  const DGST_BYTES: usize = Self::FORS_MSG_BYTES + Self::TREE_BYTES + Self::LEAF_BYTES;
+
+  /// SHA-512 replaces SHA-256 for every hash but F and PRF from n = 24
+  const SHA512: bool = Self::N >= 24;
+}
+
+/// Defines a marker type for each parameter set, its dimensions come from
+/// the matching params/*.rs file
+macro_rules! parameter_sets {
+  ($($name:ident: $level:ident, $hash:ident, $thash:ident, $id:literal;)*) => {
+    $(
+      #[doc = concat!("Marker type for the `", $id, "` parameter set")]
+      #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+      pub struct $name;
+
+      impl private::Sealed for $name {}
+
+      impl ParameterSet for $name {
+        const N: usize = $level::SPX_N;
+        const FULL_HEIGHT: usize = $level::SPX_FULL_HEIGHT;
+        const D: usize = $level::SPX_D;
+        const FORS_HEIGHT: usize = $level::SPX_FORS_HEIGHT;
+        const FORS_TREES: usize = $level::SPX_FORS_TREES;
+        const WOTS_W: usize = $level::SPX_WOTS_W;
+        const HASH: HashFamily = HashFamily::$hash;
+        const THASH: Thash = Thash::$thash;
+      }
+
+      const _: () = validate::<$name>();
+    )*
   }
-};
+}
+
+/// Invokes the macro $m with every parameter set as 
+/// `Name: level, HashFamily, Thash, "identifier";`
+macro_rules! with_parameter_sets {
+  ($m:ident) => {
+    $m! {
+      Haraka128fRobust: f128, Haraka, Robust, "sphincs-haraka-128f-robust";
+      Haraka128fSimple: f128, Haraka, Simple, "sphincs-haraka-128f-simple";
+      Haraka128sRobust: s128, Haraka, Robust, "sphincs-haraka-128s-robust";
+      Haraka128sSimple: s128, Haraka, Simple, "sphincs-haraka-128s-simple";
+      Haraka192fRobust: f192, Haraka, Robust, "sphincs-haraka-192f-robust";
+      Haraka192fSimple: f192, Haraka, Simple, "sphincs-haraka-192f-simple";
+      Haraka192sRobust: s192, Haraka, Robust, "sphincs-haraka-192s-robust";
+      Haraka192sSimple: s192, Haraka, Simple, "sphincs-haraka-192s-simple";
+      Haraka256fRobust: f256, Haraka, Robust, "sphincs-haraka-256f-robust";
+      Haraka256fSimple: f256, Haraka, Simple, "sphincs-haraka-256f-simple";
+      Haraka256sRobust: s256, Haraka, Robust, "sphincs-haraka-256s-robust";
+      Haraka256sSimple: s256, Haraka, Simple, "sphincs-haraka-256s-simple";
+      Sha2_128fRobust: f128, Sha2, Robust, "sphincs-sha2-128f-robust";
+      Sha2_128fSimple: f128, Sha2, Simple, "sphincs-sha2-128f-simple";
+      Sha2_128sRobust: s128, Sha2, Robust, "sphincs-sha2-128s-robust";
+      Sha2_128sSimple: s128, Sha2, Simple, "sphincs-sha2-128s-simple";
+      Sha2_192fRobust: f192, Sha2, Robust, "sphincs-sha2-192f-robust";
+      Sha2_192fSimple: f192, Sha2, Simple, "sphincs-sha2-192f-simple";
+      Sha2_192sRobust: s192, Sha2, Robust, "sphincs-sha2-192s-robust";
+      Sha2_192sSimple: s192, Sha2, Simple, "sphincs-sha2-192s-simple";
+      Sha2_256fRobust: f256, Sha2, Robust, "sphincs-sha2-256f-robust";
+      Sha2_256fSimple: f256, Sha2, Simple, "sphincs-sha2-256f-simple";
+      Sha2_256sRobust: s256, Sha2, Robust, "sphincs-sha2-256s-robust";
+      Sha2_256sSimple: s256, Sha2, Simple, "sphincs-sha2-256s-simple";
+      Shake128fRobust: f128, Shake, Robust, "sphincs-shake-128f-robust";
+      Shake128fSimple: f128, Shake, Simple, "sphincs-shake-128f-simple";
+      Shake128sRobust: s128, Shake, Robust, "sphincs-shake-128s-robust";
+      Shake128sSimple: s128, Shake, Simple, "sphincs-shake-128s-simple";
+      Shake192fRobust: f192, Shake, Robust, "sphincs-shake-192f-robust";
+      Shake192fSimple: f192, Shake, Simple, "sphincs-shake-192f-simple";
+      Shake192sRobust: s192, Shake, Robust, "sphincs-shake-192s-robust";
+      Shake192sSimple: s192, Shake, Simple, "sphincs-shake-192s-simple";
+      Shake256fRobust: f256, Shake, Robust, "sphincs-shake-256f-robust";
+      Shake256fSimple: f256, Shake, Simple, "sphincs-shake-256f-simple";
+      Shake256sRobust: s256, Shake, Robust, "sphincs-shake-256s-robust";
+      Shake256sSimple: s256, Shake, Simple, "sphincs-shake-256s-simple";
+    }
+  }
+}
+pub(crate) use with_parameter_sets;
+
+with_parameter_sets!(parameter_sets);
+
+/// The parameter set chosen by the cargo features, used by the default api
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Selected;
+
+impl private::Sealed for Selected {}
+
+impl ParameterSet for Selected {
+  const N: usize = SPX_N;
+  const FULL_HEIGHT: usize = SPX_FULL_HEIGHT;
+  const D: usize = SPX_D;
+  const FORS_HEIGHT: usize = SPX_FORS_HEIGHT;
+  const FORS_TREES: usize = SPX_FORS_TREES;
+  const WOTS_W: usize = SPX_WOTS_W;
+  const HASH: HashFamily = if cfg!(feature = "sha2") { HashFamily::Sha2 }
+    else if cfg!(feature = "shake") { HashFamily::Shake }
+    else { HashFamily::Haraka };
+  const THASH: Thash = if cfg!(feature = "simple") { Thash::Simple }
+    else { Thash::Robust };
+}
+
+const _: () = validate::<Selected>();
+
+/// Checks a parameter set is supported, panicking at compile time when
+/// evaluated in a constant. The dimensions must fit the stack buffers sized
+/// by the SPX_MAX_* constants, the two byte keypair address and the 64 bit
+/// tree index.
+#[doc(hidden)]
+pub const fn validate<P: ParameterSet>()
+{
+  assert!(P::N == 16 || P::N == 24 || P::N == 32, "n must be 16, 24 or 32");
+  assert!(
+    P::WOTS_W == 4 || P::WOTS_W == 16 || P::WOTS_W == 256, 
+    "w must be 4, 16 or 256"
+  );
+  assert!(P::D > 0, "d must be at least 1");
+  assert!(P::FULL_HEIGHT % P::D == 0, "h must be divisible by d");
+  assert!(P::TREE_HEIGHT > 0, "h / d must be at least 1");
+  assert!(
+    P::TREE_HEIGHT <= SPX_MAX_TREE_HEIGHT, 
+    "h / d must fit the two byte keypair address, at most 16"
+  );
+  assert!(P::TREE_BITS <= 64, "h - h / d must be at most 64");
+  assert!(P::FORS_HEIGHT > 0, "log t must be at least 1");
+  assert!(P::FORS_HEIGHT <= SPX_MAX_FORS_HEIGHT, "log t must be at most 16");
+  assert!(P::FORS_TREES > 0, "k must be at least 1");
+  assert!(P::FORS_TREES <= SPX_MAX_FORS_TREES, "k must be at most 35");
+  assert!(P::WOTS_LEN <= SPX_MAX_WOTS_LEN);
+  assert!(P::DGST_BYTES <= SPX_MAX_DGST_BYTES);
+  assert!(P::BYTES <= SPX_MAX_BYTES, "signature must be at most 49856 bytes");
+}
+
+/// Defines a marker type for a custom parameter set from n, h, d, log t, k
+/// and w, the derived sizes follow from them. The set is checked when the 
+/// crate using it is compiled, see `validate` in params.rs for the limits.
+///
+/// The marker can be used wherever a [`generic::ParameterSet`] is expected,
+/// the hash family and tweakable hash construction are named by their 
+/// [`generic::HashFamily`] and [`generic::Thash`] variants.
+///
+/// Example:
+/// ```
+/// pqc_sphincsplus::custom_parameter_set! {
+///   /// SHA2 128 bit set with fewer layers and larger FORS trees
+///   pub struct Sha2Custom {
+///     hash: Sha2, thash: Simple, n: 16, h: 60, d: 5, log_t: 10, k: 25, w: 16
+///   }
+/// }
+///
+/// use pqc_sphincsplus::generic::ParameterSet;
+/// assert_eq!(Sha2Custom::TREE_HEIGHT, 12);
+/// ```
+///
+/// Invalid sets fail to compile, here h is not divisible by d:
+/// ```compile_fail
+/// pqc_sphincsplus::custom_parameter_set! {
+///   struct Invalid {
+///     hash: Shake, thash: Simple, n: 16, h: 63, d: 5, log_t: 10, k: 25, w: 16
+///   }
+/// }
+/// # let _ = Invalid;
+/// ```
+///
+/// [`generic::ParameterSet`]: crate::generic::ParameterSet
+/// [`generic::HashFamily`]: crate::generic::HashFamily
+/// [`generic::Thash`]: crate::generic::Thash
+#[macro_export]
+macro_rules! custom_parameter_set {
+  (
+    $(#[$attr:meta])*
+    $vis:vis struct $name:ident {
+      hash: $hash:ident, thash: $thash:ident, 
+      n: $n:expr, h: $h:expr, d: $d:expr, log_t: $log_t:expr, k: $k:expr, 
+      w: $w:expr $(,)?
+    }
+  ) => {
+    $(#[$attr])*
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    $vis struct $name;
+
+    impl $crate::__private::Sealed for $name {}
+
+    impl $crate::generic::ParameterSet for $name {
+      const N: usize = $n;
+      const FULL_HEIGHT: usize = $h;
+      const D: usize = $d;
+      const FORS_HEIGHT: usize = $log_t;
+      const FORS_TREES: usize = $k;
+      const WOTS_W: usize = $w;
+      const HASH: $crate::generic::HashFamily = $crate::generic::HashFamily::$hash;
+      const THASH: $crate::generic::Thash = $crate::generic::Thash::$thash;
+    }
+
+    const _: () = $crate::__private::validate::<$name>();
+  }
+}
 
-pub const HASH: &str = if cfg!(feature = "sha2") { "sha2" } 
-else if cfg!(feature = "shake") { "shake" } 
+pub const HASH: &str = if cfg!(feature = "sha2") { "sha2" }
+else if cfg!(feature = "shake") { "shake" }
 else { "haraka" };
 
 pub const MODE: &str = if cfg!(feature = "s128") { "128s" }
-else if cfg!(feature = "f128") { "128f" } 
-else if cfg!(feature = "s192") { "192s" } 
-else if cfg!(feature = "f192") { "192f" } 
-else if cfg!(feature = "s256") { "256s" } 
+else if cfg!(feature = "f128") { "128f" }
+else if cfg!(feature = "s192") { "192s" }
+else if cfg!(feature = "f192") { "192f" }
+else if cfg!(feature = "s256") { "256s" }
 else { "256f" };
 
-pub const THASH: &str = if cfg!(feature = "simple") { "simple" } 
-else { "robust" }; 
\ No newline at end of file
+pub const THASH: &str = if cfg!(feature = "simple") { "simple" }
+else { "robust" };
@@ -7,4 +7,5 @@ pub const SPX_D: usize = 22;
 /// FORS tree dimensions.
 pub const SPX_FORS_HEIGHT: usize = 8;
 pub const SPX_FORS_TREES: usize = 33;
-
+/// Winternitz parameter.
+pub const SPX_WOTS_W: usize = 16;
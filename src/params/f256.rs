@@ -7,4 +7,5 @@ pub const SPX_D: usize = 17;
 /// FORS tree dimensions.
 pub const SPX_FORS_HEIGHT: usize = 9;
 pub const SPX_FORS_TREES: usize = 35;
-
+/// Winternitz parameter.
+pub const SPX_WOTS_W: usize = 16;
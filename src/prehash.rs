@@ -0,0 +1,129 @@
+use sha256::{Digest, Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512, Shake128, Shake256};
+use sha3::digest::{Update, ExtendableOutput, XofReader};
+use crate::api::SigError;
+use crate::sign::slh_dsa_header;
+
+/// The longest supported digest, SHA-512, SHA3-512 and SHAKE256 output 64 bytes
+pub(crate) const MAX_DIGEST_BYTES: usize = 64;
+
+/// The digest algorithm of a pre-hash (HashSLH-DSA) signature, every 
+/// approved hash function or XOF listed in FIPS 205 section 10.2.2
+/// 
+/// Its OID is part of the signed message, so a pre-hash signature never 
+/// verifies as a pure signature or as a pre-hash signature under a 
+/// different digest algorithm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PreHash {
+  /// SHA-256, 32 byte digest
+  Sha256,
+  /// SHA-512, 64 byte digest
+  Sha512,
+  /// SHAKE128 with 256 bits of output
+  Shake128,
+  /// SHAKE256 with 512 bits of output
+  Shake256,
+  /// SHA-224, 28 byte digest
+  Sha224,
+  /// SHA-384, 48 byte digest
+  Sha384,
+  /// SHA-512/224, 28 byte digest
+  Sha512_224,
+  /// SHA-512/256, 32 byte digest
+  Sha512_256,
+  /// SHA3-224, 28 byte digest
+  Sha3_224,
+  /// SHA3-256, 32 byte digest
+  Sha3_256,
+  /// SHA3-384, 48 byte digest
+  Sha3_384,
+  /// SHA3-512, 64 byte digest
+  Sha3_512
+}
+
+impl PreHash {
+  /// DER encoding of the digest algorithm OID as used in FIPS 205
+  pub const fn oid(&self) -> &'static [u8; 11]
+  {
+    match self {
+      PreHash::Sha256 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01],
+      PreHash::Sha384 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02],
+      PreHash::Sha512 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03],
+      PreHash::Sha224 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04],
+      PreHash::Sha512_224 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x05],
+      PreHash::Sha512_256 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x06],
+      PreHash::Sha3_224 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x07],
+      PreHash::Sha3_256 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x08],
+      PreHash::Sha3_384 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x09],
+      PreHash::Sha3_512 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x0A],
+      PreHash::Shake128 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x0B],
+      PreHash::Shake256 => 
+        &[0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x0C]
+    }
+  }
+
+  /// Length in bytes of the digest that gets signed
+  pub const fn digest_len(&self) -> usize
+  {
+    match self {
+      PreHash::Sha224 | PreHash::Sha512_224 | PreHash::Sha3_224 => 28,
+      PreHash::Sha256 | PreHash::Sha512_256 | PreHash::Sha3_256 
+        | PreHash::Shake128 => 32,
+      PreHash::Sha384 | PreHash::Sha3_384 => 48,
+      PreHash::Sha512 | PreHash::Sha3_512 | PreHash::Shake256 => 64
+    }
+  }
+
+  /// Hashes the message into the front of out, returns the digest length
+  pub(crate) fn digest(&self, msg: &[u8], out: &mut [u8; MAX_DIGEST_BYTES]) -> usize
+  {
+    let len = self.digest_len();
+    match self {
+      PreHash::Sha224 => out[..len].copy_from_slice(&Sha224::digest(msg)),
+      PreHash::Sha256 => out[..len].copy_from_slice(&Sha256::digest(msg)),
+      PreHash::Sha384 => out[..len].copy_from_slice(&Sha384::digest(msg)),
+      PreHash::Sha512 => out[..len].copy_from_slice(&Sha512::digest(msg)),
+      PreHash::Sha512_224 => out[..len].copy_from_slice(&Sha512_224::digest(msg)),
+      PreHash::Sha512_256 => out[..len].copy_from_slice(&Sha512_256::digest(msg)),
+      PreHash::Sha3_224 => out[..len].copy_from_slice(&Sha3_224::digest(msg)),
+      PreHash::Sha3_256 => out[..len].copy_from_slice(&Sha3_256::digest(msg)),
+      PreHash::Sha3_384 => out[..len].copy_from_slice(&Sha3_384::digest(msg)),
+      PreHash::Sha3_512 => out[..len].copy_from_slice(&Sha3_512::digest(msg)),
+      PreHash::Shake128 => {
+        let mut hasher = Shake128::default();
+        hasher.update(msg);
+        hasher.finalize_xof().read(&mut out[..len]);
+      },
+      PreHash::Shake256 => {
+        let mut hasher = Shake256::default();
+        hasher.update(msg);
+        hasher.finalize_xof().read(&mut out[..len]);
+      }
+    }
+    len
+  }
+}
+
+/// The domain separator and context length that start a pre-hash message
+/// 
+/// The full message signed is 0x01 || len(ctx) || ctx || OID || PH(M).
+pub(crate) fn prehash_header(
+  ctx: &[u8], ph: PreHash, digest: &[u8]
+) -> Result<[u8; 2], SigError>
+{
+  if digest.len() != ph.digest_len() {
+    return Err(SigError::DigestLength);
+  }
+  slh_dsa_header(1, ctx)
+}
@@ -1,6 +1,21 @@
-use rand::prelude::*;
+use rand_core::{CryptoRng, RngCore};
 
-pub(crate) fn randombytes(x: &mut [u8], len: usize)
+pub(crate) fn randombytes<R>(rng: &mut R, x: &mut [u8], len: usize)
+  where R: RngCore + CryptoRng + ?Sized
 {
-  thread_rng().fill_bytes(&mut x[..len])
-}
\ No newline at end of file
+  rng.fill_bytes(&mut x[..len])
+}
+
+/// The RNG used when the caller does not provide one
+#[cfg(feature = "std")]
+pub(crate) fn default_rng() -> rand::rngs::ThreadRng
+{
+  rand::thread_rng()
+}
+
+/// The RNG used when the caller does not provide one
+#[cfg(all(feature = "getrandom", not(feature = "std")))]
+pub(crate) fn default_rng() -> rand_core::OsRng
+{
+  rand_core::OsRng
+}
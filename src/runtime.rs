@@ -0,0 +1,226 @@
+use core::fmt;
+use rand_core::{CryptoRng, RngCore};
+use crate::api::{SigError, SignMode};
+use crate::context::Variant;
+use crate::params::{self, ParameterSet, SPX_MAX_N, with_parameter_sets};
+use crate::randombytes::*;
+use crate::sign::*;
+
+macro_rules! runtime_parameter_sets {
+  ($($name:ident: $level:ident, $hash:ident, $thash:ident, $id:literal;)*) => {
+    /// A SPHINCS+ parameter set chosen at runtime
+    ///
+    /// Names one of the marker types implementing the type-level 
+    /// [`generic::ParameterSet`](crate::generic::ParameterSet) trait.
+    ///
+    /// Every parameter set is compiled in regardless of the cargo features,
+    /// which only pick the one used by the rest of the api. Keys and
+    /// signatures are plain byte buffers sized by the matching `*_bytes`
+    /// method.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use pqc_sphincsplus::*;
+    /// let params: ParameterSetId = "sphincs-shake-128f-simple".parse().unwrap();
+    /// let mut pk = vec![0u8; params.public_key_bytes()];
+    /// let mut sk = vec![0u8; params.secret_key_bytes()];
+    /// let mut sig = vec![0u8; params.signature_bytes()];
+    /// params.keypair_with_rng(&mut pk, &mut sk, &mut rand::thread_rng()).unwrap();
+    /// params.sign_into_with_mode(&mut sig, b"hello", &sk, SignMode::Deterministic).unwrap();
+    /// assert!(params.verify(&sig, b"hello", &pk).is_ok());
+    /// ```
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum ParameterSetId {
+      $($name,)*
+    }
+
+    impl ParameterSetId {
+      /// Every parameter set, in the order of the SPHINCS+ KAT files
+      pub const ALL: &'static [ParameterSetId] = &[$(ParameterSetId::$name,)*];
+
+      /// The parameter set chosen by the cargo features
+      pub const SELECTED: ParameterSetId = {
+        let mut i = 0;
+        loop {
+          if ParameterSetId::ALL[i].matches::<params::Selected>() {
+            break ParameterSetId::ALL[i];
+          }
+          i += 1;
+        }
+      };
+
+      /// The name used by the reference implementation, for example
+      /// `sphincs-sha2-128f-simple`
+      pub const fn name(self) -> &'static str
+      {
+        match self {
+          $(ParameterSetId::$name => $id,)*
+        }
+      }
+
+      /// Looks up a parameter set by its name, see [`ParameterSetId::name`]
+      pub fn from_name(name: &str) -> Option<Self>
+      {
+        Self::ALL.iter().copied().find(|params| params.name() == name)
+      }
+
+      /// Length of the hashes and each of the three seeds in bytes, n
+      pub const fn seed_bytes(self) -> usize
+      {
+        match self {
+          $(ParameterSetId::$name => <params::$name as ParameterSet>::N,)*
+        }
+      }
+
+      /// Length of a public key in bytes
+      pub const fn public_key_bytes(self) -> usize
+      {
+        match self {
+          $(ParameterSetId::$name => <params::$name as ParameterSet>::PK_BYTES,)*
+        }
+      }
+
+      /// Length of a secret key in bytes
+      pub const fn secret_key_bytes(self) -> usize
+      {
+        match self {
+          $(ParameterSetId::$name => <params::$name as ParameterSet>::SK_BYTES,)*
+        }
+      }
+
+      /// Length of a signature in bytes
+      pub const fn signature_bytes(self) -> usize
+      {
+        match self {
+          $(ParameterSetId::$name => <params::$name as ParameterSet>::BYTES,)*
+        }
+      }
+
+      const fn matches<P: ParameterSet>(self) -> bool
+      {
+        match self {
+          $(ParameterSetId::$name => {
+            <params::$name as ParameterSet>::N == P::N
+              && <params::$name as ParameterSet>::FULL_HEIGHT == P::FULL_HEIGHT
+              && <params::$name as ParameterSet>::D == P::D
+              && <params::$name as ParameterSet>::FORS_HEIGHT == P::FORS_HEIGHT
+              && <params::$name as ParameterSet>::FORS_TREES == P::FORS_TREES
+              && <params::$name as ParameterSet>::WOTS_W == P::WOTS_W
+              && <params::$name as ParameterSet>::HASH as u8 == P::HASH as u8
+              && <params::$name as ParameterSet>::THASH as u8 == P::THASH as u8
+          })*
+        }
+      }
+
+      /// Deterministically generates a keypair into pk and sk from its three
+      /// n byte seeds, see [`crate::keypair_from_seeds`]
+      pub fn keypair_from_seeds(
+        self, pk: &mut [u8], sk: &mut [u8],
+        sk_seed: &[u8], sk_prf: &[u8], pk_seed: &[u8]
+      ) -> Result<(), SigError>
+      {
+        let n = self.seed_bytes();
+        let mut seed = [0u8; 3*SPX_MAX_N];
+        for (i, part) in [sk_seed, sk_prf, pk_seed].iter().enumerate() {
+          check_len(part, n, SigError::SeedLength)?;
+          seed[i*n..(i+1)*n].copy_from_slice(part);
+        }
+        match self {
+          $(ParameterSetId::$name => seed_keypair::<params::$name>(pk, sk, &seed),)*
+        }
+      }
+
+      /// Signs msg with the secret key sk into sig, the signing randomness
+      /// is chosen according to mode
+      pub fn sign_into_with_mode(
+        self, sig: &mut [u8], msg: &[u8], sk: &[u8], mode: SignMode
+      ) -> Result<(), SigError>
+      {
+        match self {
+          $(ParameterSetId::$name => sign_into::<params::$name>(sig, msg, sk, mode),)*
+        }
+      }
+
+      /// Verifies a detached signature over msg under the public key pk
+      pub fn verify(self, sig: &[u8], msg: &[u8], pk: &[u8]) -> Result<(), SigError>
+      {
+        match self {
+          $(ParameterSetId::$name => verify::<params::$name>(sig, msg, pk),)*
+        }
+      }
+    }
+  }
+}
+
+with_parameter_sets!(runtime_parameter_sets);
+
+impl ParameterSetId {
+  /// Generates a keypair into pk and sk using the system random number
+  /// generator
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn keypair(self, pk: &mut [u8], sk: &mut [u8]) -> Result<(), SigError>
+  {
+    self.keypair_with_rng(pk, sk, &mut default_rng())
+  }
+
+  /// Generates a keypair into pk and sk using the provided random number
+  /// generator
+  pub fn keypair_with_rng<R>(
+    self, pk: &mut [u8], sk: &mut [u8], rng: &mut R
+  ) -> Result<(), SigError>
+    where R: RngCore + CryptoRng
+  {
+    let n = self.seed_bytes();
+    let mut seed = [0u8; 3*SPX_MAX_N];
+    randombytes(rng, &mut seed, 3*n);
+    self.keypair_from_seeds(pk, sk, &seed[..n], &seed[n..2*n], &seed[2*n..3*n])
+  }
+
+  /// Signs msg with the secret key sk into sig using the system random
+  /// number generator
+  #[cfg(any(feature = "std", feature = "getrandom"))]
+  pub fn sign_into(self, sig: &mut [u8], msg: &[u8], sk: &[u8]) -> Result<(), SigError>
+  {
+    self.sign_into_with_mode(sig, msg, sk, SignMode::Hedged(&mut default_rng()))
+  }
+}
+
+impl fmt::Display for ParameterSetId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+impl core::str::FromStr for ParameterSetId {
+  type Err = SigError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    Self::from_name(name).ok_or(SigError::UnknownParameterSet)
+  }
+}
+
+fn seed_keypair<P: ParameterSet>(
+  pk: &mut [u8], sk: &mut [u8], seed: &[u8]
+) -> Result<(), SigError>
+{
+  check_len(pk, P::PK_BYTES, SigError::PublicKeyLength)?;
+  check_len(sk, P::SK_BYTES, SigError::SecretKeyLength)?;
+  spx_seed_keypair::<P>(pk, sk, &seed[..3*P::N])
+}
+
+fn sign_into<P: ParameterSet>(
+  sig: &mut [u8], mut msg: &[u8], sk: &[u8], mode: SignMode
+) -> Result<(), SigError>
+{
+  check_len(sig, P::BYTES, SigError::SignatureLength)?;
+  check_len(sk, P::SK_BYTES, SigError::SecretKeyLength)?;
+  let optrand = spx_optrand::<P>(sk, mode)?;
+  spx_sign_msg::<P>(sig, &mut msg, sk, &optrand, Variant::Sphincs)
+}
+
+fn verify<P: ParameterSet>(sig: &[u8], msg: &[u8], pk: &[u8]) -> Result<(), SigError>
+{
+  check_len(sig, P::BYTES, SigError::SignatureLength)?;
+  check_len(pk, P::PK_BYTES, SigError::PublicKeyLength)?;
+  spx_verify::<P>(sig, msg, pk)
+}
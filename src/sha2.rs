@@ -4,7 +4,6 @@ use crate::params::*;
 use crate::utils::*;
 use sha256::digest::generic_array::GenericArray;
 
-#[cfg(any(feature="f128", feature="s128"))]
 pub const SPX_SHA256_BLOCK_BYTES: usize = 64;
 pub const SPX_SHA256_OUTPUT_BYTES: usize = 32;  /* This does not necessarily equal SPX_N */
 
@@ -13,15 +12,17 @@ pub const SPX_SHA512_OUTPUT_BYTES: usize = 64;
 
 pub const SPX_SHA256_ADDR_BYTES: usize = 22;
 
-#[cfg(not(any(feature="f128", feature="s128")))]
-pub const SPX_SHAX_OUTPUT_BYTES: usize = SPX_SHA512_OUTPUT_BYTES;
-#[cfg(not(any(feature="f128", feature="s128")))]
-pub const SPX_SHAX_BLOCK_BYTES: usize = SPX_SHA512_BLOCK_BYTES;
+/// SHA-X is SHA-512 for n >= 24 and SHA-256 otherwise
+pub const fn shax_output_bytes<P: ParameterSet>() -> usize {
+  if P::SHA512 { SPX_SHA512_OUTPUT_BYTES } else { SPX_SHA256_OUTPUT_BYTES }
+}
+
+pub const fn shax_block_bytes<P: ParameterSet>() -> usize {
+  if P::SHA512 { SPX_SHA512_BLOCK_BYTES } else { SPX_SHA256_BLOCK_BYTES }
+}
 
-#[cfg(any(feature="f128", feature="s128"))]
-pub const SPX_SHAX_OUTPUT_BYTES: usize = SPX_SHA256_OUTPUT_BYTES;
-#[cfg(any(feature="f128", feature="s128"))]
-pub const SPX_SHAX_BLOCK_BYTES: usize = SPX_SHA256_BLOCK_BYTES;
+/// Longest mgf1 seed, R || PK.seed || SHA-X(R || PK || M) for n = 32
+const MGF1_MAX_INLEN: usize = 2 * SPX_MAX_N + SPX_SHA512_OUTPUT_BYTES;
 
 
 const IV_256: [u8; 32] = [
@@ -56,6 +57,7 @@ pub fn store_bigendian_64(x: &mut[u8], u: u64) {
   x[..8].copy_from_slice(&u.to_be_bytes());
 }
 
+#[inline]
 fn crypto_hashblocks_sha256(
   statebytes: &mut [u8], input: &[u8], mut inlen: usize
 ) -> usize  
@@ -78,9 +80,10 @@ fn crypto_hashblocks_sha256(
     store_bigendian_32(&mut statebytes[i*4..], state[i]);
   }
 
-  return inlen;
+  inlen
 }
 
+#[inline]
 fn crypto_hashblocks_sha512(statebytes: &mut[u8],input: &[u8], mut inlen: usize) -> usize
 {
   let mut state = [0u64; 8];
@@ -100,25 +103,19 @@ fn crypto_hashblocks_sha512(statebytes: &mut[u8],input: &[u8], mut inlen: usize)
   for i in 0..8 {
     store_bigendian_64(&mut statebytes[i*8..],state[i]);
   }
-  return inlen;
+  inlen
 }
 
+#[inline]
 pub fn sha256_inc_init(state: &mut[u8]) {
-  for i in 0..32 {
-    state[i] = IV_256[i];
-  }
-  for i in 32..40 {
-    state[i] = 0;
-  }
+  state[..32].copy_from_slice(&IV_256);
+  state[32..40].fill(0);
 }
 
+#[inline]
 pub fn sha512_inc_init(state: &mut[u8]) {
-  for i in 0..64 {
-    state[i] = IV_512[i];
-  }
-  for i in 64..72 {
-    state[i] = 0;
-  }
+  state[..64].copy_from_slice(&IV_512);
+  state[64..72].fill(0);
 }
 
 pub fn sha256_inc_blocks(state: &mut [u8], input: &[u8], inblocks: usize) {
@@ -135,6 +132,7 @@ pub fn sha512_inc_blocks(state: &mut[u8], input: &[u8], inblocks: usize) {
   store_bigendian_64(&mut state[64..], bytes);
 }
 
+#[inline]
 pub fn sha256_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inlen: usize) {
   let mut padded = [0u8; 128];
   let bytes = load_bigendian_64(&state[32..]) + inlen as u64;
@@ -146,7 +144,7 @@ pub fn sha256_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inl
   idx -= inlen;
 
   padded[..inlen].copy_from_slice(&input[idx..idx+inlen]);
-  padded[inlen as usize] = 0x80;
+  padded[inlen] = 0x80;
 
   if inlen < 56 {
     padded[(inlen+1)..56].fill(0);
@@ -174,6 +172,7 @@ pub fn sha256_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inl
   out[..32].copy_from_slice(&state[..32]);
 }
 
+#[inline]
 pub fn sha512_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inlen: usize) 
 {
   let mut padded = [0u8; 256];
@@ -186,7 +185,7 @@ pub fn sha512_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inl
   idx -= inlen;
 
   padded[..inlen].copy_from_slice(&input[idx..idx+inlen]);
-  padded[inlen as usize] = 0x80;
+  padded[inlen] = 0x80;
 
   if inlen < 112 {
     padded[(inlen+1)..119].fill(0);
@@ -216,12 +215,53 @@ pub fn sha512_inc_finalize(out: &mut[u8], state: &mut[u8], input: &[u8], mut inl
   out[..64].copy_from_slice(&state[..64]);
 }
 
+/// Finalizes the state over addr || input when both leave room for the 
+/// padding in a single block, which is the case for F, H and PRF. The 
+/// padded block is built in place instead of copying through a buffer.
+#[inline]
+pub fn sha256_inc_finalize_short(
+  out: &mut[u8], state: &mut[u8], addr: &[u8], input: &[u8]
+)
+{
+  let mut padded = [0u8; SPX_SHA256_BLOCK_BYTES];
+  let inlen = addr.len() + input.len();
+  let bytes = load_bigendian_64(&state[32..]) + inlen as u64;
+
+  padded[..addr.len()].copy_from_slice(addr);
+  padded[addr.len()..inlen].copy_from_slice(input);
+  padded[inlen] = 0x80;
+  store_bigendian_64(&mut padded[56..], bytes << 3);
+  crypto_hashblocks_sha256(state, &padded, SPX_SHA256_BLOCK_BYTES);
+  out[..32].copy_from_slice(&state[..32]);
+}
+
+/// SHA-512 counterpart of [`sha256_inc_finalize_short`]
+#[inline]
+pub fn sha512_inc_finalize_short(
+  out: &mut[u8], state: &mut[u8], addr: &[u8], input: &[u8]
+)
+{
+  let mut padded = [0u8; SPX_SHA512_BLOCK_BYTES];
+  let inlen = addr.len() + input.len();
+  let bytes = load_bigendian_64(&state[64..]) + inlen as u64;
+
+  padded[..addr.len()].copy_from_slice(addr);
+  padded[addr.len()..inlen].copy_from_slice(input);
+  padded[inlen] = 0x80;
+  padded[119] = (bytes >> 61) as u8;
+  store_bigendian_64(&mut padded[120..], bytes << 3);
+  crypto_hashblocks_sha512(state, &padded, SPX_SHA512_BLOCK_BYTES);
+  out[..64].copy_from_slice(&state[..64]);
+}
+
+#[inline(always)]
 pub fn sha256(out: &mut [u8], input: &[u8], inlen: usize) {
   let mut state = [0u8; 40];
   sha256_inc_init(&mut state);
   sha256_inc_finalize(out, &mut state, input, inlen);
 }
 
+#[inline]
 pub fn sha512(out: &mut [u8], input: &[u8], inlen: usize) {
   let mut state = [0u8; 72];
   sha512_inc_init(&mut state);
@@ -229,58 +269,58 @@ pub fn sha512(out: &mut [u8], input: &[u8], inlen: usize) {
 }
 
 /// mgf1 function based on the SHA-256 hash function
-/// Note that inlen should be sufficiently small that it still allows for
-/// an array to be allocated on the stack. Typically 'input' is merely a seed.
+/// Note that the input is merely a seed, at most MGF1_MAX_INLEN bytes.
 /// Outputs outlen number of bytes
-#[cfg(feature = "robust")]
+#[inline(always)]
 pub fn mgf1_256(out: &mut[u8], outlen: usize, input: &[u8])
 {
-  const INLEN: usize = SPX_N + SPX_SHA256_ADDR_BYTES;
-  let mut inbuf = [0u8; INLEN + 4];
+  let inlen = input.len();
+  let mut inbuf = [0u8; MGF1_MAX_INLEN + 4];
   let mut outbuf = [0u8; SPX_SHA256_OUTPUT_BYTES];
 
-  inbuf[..INLEN].copy_from_slice(&input[..INLEN]);
+  inbuf[..inlen].copy_from_slice(input);
 
   // While we can fit in at least another full block of SHA256 output..
   let mut i = 0;
   let mut idx = 0;
   while (i+1)*SPX_SHA256_OUTPUT_BYTES <= outlen {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha256(&mut out[idx..], &inbuf, INLEN + 4);
+    u32_to_bytes(&mut inbuf[inlen..], i as u32);
+    sha256(&mut out[idx..], &inbuf, inlen + 4);
     idx += SPX_SHA256_OUTPUT_BYTES;
     i += 1;
   }
   // Until we cannot anymore, and we fill the remainder.
   if outlen > i*SPX_SHA256_OUTPUT_BYTES {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha256(&mut outbuf, &inbuf, INLEN + 4);
+    u32_to_bytes(&mut inbuf[inlen..], i as u32);
+    sha256(&mut outbuf, &inbuf, inlen + 4);
     let end = outlen - i*SPX_SHA256_OUTPUT_BYTES;
     out[idx..idx+end].copy_from_slice(&outbuf[..end]);
   }
 }
 
 // mgf1 function based on the SHA-512 hash function
-#[cfg(all(feature = "robust", not(any(feature="f128", feature="s128"))))]
+#[inline]
 pub fn mgf1_512(out: &mut[u8], outlen: usize, input: &[u8])
 {
-  const INLEN: usize = SPX_N + SPX_SHA256_ADDR_BYTES;
-  let mut inbuf = [0u8; INLEN + 4];
+  let inlen = input.len();
+  let mut inbuf = [0u8; MGF1_MAX_INLEN + 4];
   let mut outbuf = [0u8; SPX_SHA512_OUTPUT_BYTES];
-  inbuf[..INLEN].copy_from_slice(&input[..INLEN]);
 
-  // While we can fit in at least another fu64 block of SHA512 output..
+  inbuf[..inlen].copy_from_slice(input);
+
+  // While we can fit in at least another full block of SHA512 output..
   let mut i = 0;
   let mut idx = 0;
   while (i+1)*SPX_SHA512_OUTPUT_BYTES <= outlen {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha512(&mut out[idx..], &inbuf, INLEN + 4);
+    u32_to_bytes(&mut inbuf[inlen..], i as u32);
+    sha512(&mut out[idx..], &inbuf, inlen + 4);
     idx += SPX_SHA512_OUTPUT_BYTES;
     i += 1;
   }
   // Until we cannot anymore, and we fill the remainder.
   if outlen > i*SPX_SHA512_OUTPUT_BYTES {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha512(&mut outbuf, &inbuf, INLEN + 4);
+    u32_to_bytes(&mut inbuf[inlen..], i as u32);
+    sha512(&mut outbuf, &inbuf, inlen + 4);
     let end = outlen - i*SPX_SHA512_OUTPUT_BYTES;
     out[idx..idx+end].copy_from_slice(&outbuf[..end]);
   }
@@ -289,82 +329,24 @@ pub fn mgf1_512(out: &mut[u8], outlen: usize, input: &[u8])
 /// Absorb the constant pub_seed using one round of the compression function
 /// This initializes state_seeded and state_seeded_512, which can then be
 /// reused input thash
-pub fn seed_state(ctx: &mut SpxCtx) {
+pub fn seed_state<P: ParameterSet>(ctx: &mut SpxCtx) {
   let mut block = [0u8; SPX_SHA512_BLOCK_BYTES];
 
-  block[..SPX_N].copy_from_slice(&ctx.pub_seed[..SPX_N]);
-  block[SPX_N..SPX_SHA512_BLOCK_BYTES].fill(0);
+  block[..P::N].copy_from_slice(&ctx.pub_seed[..P::N]);
+  block[P::N..SPX_SHA512_BLOCK_BYTES].fill(0);
 
   // block has been properly initialized for both SHA-256 and SHA-512
   sha256_inc_init(&mut ctx.state_seeded);
   sha256_inc_blocks(&mut ctx.state_seeded, &block, 1);
 
-  #[cfg(all(feature="sha2", not(any(feature="f128", feature="s128"))))]
-  {
+  if P::SHA512 {
     sha512_inc_init(&mut ctx.state_seeded_512);
     sha512_inc_blocks(&mut ctx.state_seeded_512, &block, 1);
   }
 }
 
-// TODO: Refactor and get rid of code duplication
-// inlen / buffer size is the only difference
-pub fn mgf1_256_2(out: &mut[u8], outlen: usize, input: &[u8])
-{
-  
-  const INLEN: usize = 2 * SPX_N + SPX_SHAX_OUTPUT_BYTES;
-  let mut inbuf = [0u8; INLEN + 4];
-  let mut outbuf = [0u8; SPX_SHA256_OUTPUT_BYTES];
-
-  inbuf[..INLEN].copy_from_slice(&input[..INLEN]);
-
-  // While we can fit in at least another full block of SHA256 output..
-  let mut i = 0;
-  let mut idx = 0;
-  while (i+1)*SPX_SHA256_OUTPUT_BYTES <= outlen {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha256(&mut out[idx..], &inbuf, INLEN + 4);
-    idx += SPX_SHA256_OUTPUT_BYTES;
-    i += 1;
-  }
-  // Until we cannot anymore, and we fill the remainder.
-  if outlen > i*SPX_SHA256_OUTPUT_BYTES {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha256(&mut outbuf, &inbuf, INLEN + 4);
-    let end = outlen - i*SPX_SHA256_OUTPUT_BYTES;
-    out[idx..idx+end].copy_from_slice(&outbuf[..end]);
-  }
-}
-
-pub fn mgf1_512_2(out: &mut[u8], outlen: usize, input: &[u8])
-{
-  // inlen / buffer size is the only difference
-  const INLEN: usize = 2 * SPX_N + SPX_SHAX_OUTPUT_BYTES;
-
-  let mut inbuf = [0u8; INLEN + 4];
-  let mut outbuf = [0u8; SPX_SHA512_OUTPUT_BYTES];
-  inbuf[..INLEN].copy_from_slice(&input[..INLEN]);
-
-  // While we can fit in at least another fu64 block of SHA512 output..
-  let mut i = 0;
-  let mut idx = 0;
-  while (i+1)*SPX_SHA512_OUTPUT_BYTES <= outlen {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha512(&mut out[idx..], &inbuf, INLEN + 4);
-    idx += SPX_SHA512_OUTPUT_BYTES;
-    i += 1;
-  }
-  // Until we cannot anymore, and we fill the remainder.
-  if outlen > i*SPX_SHA512_OUTPUT_BYTES {
-    u32_to_bytes(&mut inbuf[INLEN..], i as u32);
-    sha512(&mut outbuf, &inbuf, INLEN + 4);
-    let end = outlen - i*SPX_SHA512_OUTPUT_BYTES;
-    out[..end].copy_from_slice(&outbuf[..end]);
-  }
-}
-
 // TODO: mfg1 tests instead
 #[cfg(test)]
-#[cfg(all(feature = "sha2", feature = "f128", feature= "robust"))]
 mod tests {
   use super::*;
   #[test]
@@ -374,7 +356,7 @@ mod tests {
     let mut outbuf = [0u8; SPX_SHA256_OUTPUT_BYTES];
     let expected = [151, 41, 244, 77, 28, 0, 51, 80, 20, 166, 116, 190, 217, 139, 37, 105, 21, 55, 45, 28, 40, 232, 167, 118, 61, 28, 222, 215, 214, 154, 24, 82];
     sha256_inc_finalize(
-      &mut outbuf, &mut sha2_state, &buf, SPX_SHA256_ADDR_BYTES + SPX_N
+      &mut outbuf, &mut sha2_state, &buf, SPX_SHA256_ADDR_BYTES + 16
     );
     assert_eq!(outbuf, expected);
   }
@@ -1,5 +1,7 @@
-use crate::api::SigError;
-use crate::context::SpxCtx;
+#[cfg(feature = "pct")]
+use alloc::vec;
+use crate::api::{SigError, SignMode};
+use crate::context::{SpxCtx, Variant};
 use crate::params::*;
 use crate::wots::*;
 use crate::fors::*;
@@ -9,68 +11,276 @@ use crate::address::*;
 use crate::utils::*;
 use crate::merkle::*;
 use crate::randombytes::*;
+use core::marker::PhantomData;
 
-/// Generates an SPX key pair given a seed of length
+/// Generates an SPX key pair given a seed of length CRYPTO_SEEDBYTES
+/// Format seed: [SK_SEED || SK_PRF || PUB_SEED]
 /// Format sk: [SK_SEED || SK_PRF || PUB_SEED || root]
 /// Format pk: [PUB_SEED || root]
-fn crypto_sign_seed_keypair(
+#[cfg(feature = "KAT")]
+pub fn crypto_sign_seed_keypair(
   pk: &mut[u8], sk: &mut[u8], seed: &[u8]
-) -> i32
+) -> Result<(), SigError>
 {
-  let mut ctx = SpxCtx::default();
+  check_len(pk, Selected::PK_BYTES, SigError::PublicKeyLength)?;
+  check_len(sk, Selected::SK_BYTES, SigError::SecretKeyLength)?;
+  check_len(seed, CRYPTO_SEEDBYTES, SigError::SeedLength)?;
+  spx_seed_keypair::<Selected>(pk, sk, seed)
+}
+
+/// Generates an SPX key pair.
+/// Format sk: [SK_SEED || SK_PRF || PUB_SEED || root]
+/// Format pk: [PUB_SEED || root]
+#[cfg(feature = "KAT")]
+pub fn crypto_sign_keypair(
+  pk: &mut[u8], sk: &mut[u8], seed: Option<&[u8]>
+) -> Result<(), SigError>
+{
+  if let Some(deterministic) = seed {
+    crypto_sign_seed_keypair(pk, sk, deterministic)
+  } else {
+    let mut seed = [0u8; CRYPTO_SEEDBYTES];
+    randombytes(&mut default_rng(), &mut seed, CRYPTO_SEEDBYTES);
+    crypto_sign_seed_keypair(pk, sk, &seed)
+  }
+}
+
+/// Generates a detached signature of exactly CRYPTO_BYTES into sig, 
+/// the signing randomness is chosen according to mode.
+#[cfg(feature = "KAT")]
+pub fn crypto_sign_signature(
+  sig: &mut[u8], mut m: &[u8], sk: &[u8], mode: SignMode
+) -> Result<(), SigError>
+{
+  check_len(sig, Selected::BYTES, SigError::SignatureLength)?;
+  check_len(sk, Selected::SK_BYTES, SigError::SecretKeyLength)?;
+  let optrand = spx_optrand::<Selected>(sk, mode)?;
+  spx_sign_msg::<Selected>(sig, &mut m, sk, &optrand, Variant::Sphincs)
+}
+
+/// Computes the signed message sm = sig || m, sm must hold at least 
+/// CRYPTO_BYTES + m.len() bytes or [`SigError::BufferLength`] is returned. 
+/// Returns the length of the signed message.
+#[cfg(feature = "KAT")]
+pub fn crypto_sign(
+  sm: &mut[u8], m: &[u8], sk: &[u8], mode: SignMode
+) -> Result<usize, SigError>
+{
+  let smlen = CRYPTO_BYTES + m.len();
+  if sm.len() < smlen {
+    return Err(SigError::BufferLength);
+  }
+  crypto_sign_signature(&mut sm[..CRYPTO_BYTES], m, sk, mode)?;
+  sm[CRYPTO_BYTES..smlen].copy_from_slice(m);
+  Ok(smlen)
+}
+
+/// Verifies a signed message sm = sig || m and copies m out, which must 
+/// hold at least sm.len() - CRYPTO_BYTES bytes or [`SigError::BufferLength`] 
+/// is returned. Returns the message length.
+/// 
+/// On failure m is zeroed.
+#[cfg(feature = "KAT")]
+pub fn crypto_sign_open(
+  m: &mut[u8], sm: &[u8], pk: &[u8]
+) -> Result<usize, SigError>
+{
+  if sm.len() < CRYPTO_BYTES {
+    return Err(SigError::SignatureLength);
+  }
+  let mlen = sm.len() - CRYPTO_BYTES;
+  if m.len() < mlen {
+    return Err(SigError::BufferLength);
+  }
+  match crypto_sign_verify(&sm[..CRYPTO_BYTES], &sm[CRYPTO_BYTES..], pk) {
+    Ok(()) => {
+      m[..mlen].copy_from_slice(&sm[CRYPTO_BYTES..]);
+      Ok(mlen)
+    },
+    Err(err) => {
+      m.fill(0);
+      Err(err)
+    }
+  }
+}
+
+/// Verifies a detached signature and message under a given public key.
+pub fn crypto_sign_verify(sig: &[u8], msg: &[u8], pk: &[u8]) -> Result<(), SigError>
+{
+  check_len(sig, Selected::BYTES, SigError::SignatureLength)?;
+  check_len(pk, Selected::PK_BYTES, SigError::PublicKeyLength)?;
+  spx_verify::<Selected>(sig, msg, pk)
+}
+
+/// FIPS 205 slh_sign_internal, generates a detached signature of exactly
+/// CRYPTO_BYTES over the already encoded message M', without adding a
+/// domain separator or context string.
+#[cfg(all(feature = "KAT", feature = "slh-dsa"))]
+pub fn crypto_sign_signature_internal(
+  sig: &mut[u8], mut m: &[u8], sk: &[u8], mode: SignMode
+) -> Result<(), SigError>
+{
+  check_len(sig, Selected::BYTES, SigError::SignatureLength)?;
+  check_len(sk, Selected::SK_BYTES, SigError::SecretKeyLength)?;
+  let optrand = spx_optrand::<Selected>(sk, mode)?;
+  spx_sign_msg::<Selected>(sig, &mut m, sk, &optrand, Variant::SlhDsa)
+}
 
+/// FIPS 205 slh_verify_internal, verifies a detached signature over the
+/// already encoded message M'.
+#[cfg(all(feature = "KAT", feature = "slh-dsa"))]
+pub fn crypto_sign_verify_internal(
+  sig: &[u8], mut m: &[u8], pk: &[u8]
+) -> Result<(), SigError>
+{
+  check_len(sig, Selected::BYTES, SigError::SignatureLength)?;
+  check_len(pk, Selected::PK_BYTES, SigError::PublicKeyLength)?;
+  spx_verify_msg::<Selected>(sig, &mut m, pk, Variant::SlhDsa)
+}
+
+/// Returns err unless buf is exactly len bytes long
+pub(crate) fn check_len(buf: &[u8], len: usize, err: SigError) -> Result<(), SigError>
+{
+  if buf.len() == len { Ok(()) } else { Err(err) }
+}
+
+/// Key generation on correctly sized buffers, see [`crypto_sign_keypair`].
+/// The seed is 3*SPX_N bytes of the parameter set P.
+pub(crate) fn spx_seed_keypair<P: ParameterSet>(
+  pk: &mut[u8], sk: &mut[u8], seed: &[u8]
+) -> Result<(), SigError>
+{
   // Initialize SK_SEED, SK_PRF and PUB_SEED from seed.
-  sk[..CRYPTO_SEEDBYTES].copy_from_slice(&seed[..CRYPTO_SEEDBYTES]);
+  sk[..3*P::N].copy_from_slice(seed);
+
+  pk[..P::N].copy_from_slice(&sk[2*P::N..3*P::N]);
 
-  pk[..SPX_N].copy_from_slice(&sk[2*SPX_N..3*SPX_N]);
+  let root = spx_gen_root::<P>(sk);
+  sk[3*P::N..4*P::N].copy_from_slice(&root[..P::N]);
 
-  ctx.sk_seed.copy_from_slice(&sk[..SPX_N]);
-  ctx.pub_seed.copy_from_slice(&pk[..SPX_N]);
+  pk[P::N..2*P::N].copy_from_slice(&sk[3*P::N..4*P::N]);
+
+  #[cfg(feature = "pct")]
+  spx_pct_or_zeroize::<P>(pk, sk)?;
+
+  Ok(())
+}
+
+/// Runs the pairwise consistency test, zeroing both keys when it fails so 
+/// a key that failed the test is never released
+#[cfg(feature = "pct")]
+fn spx_pct_or_zeroize<P: ParameterSet>(
+  pk: &mut[u8], sk: &mut[u8]
+) -> Result<(), SigError>
+{
+  spx_pct::<P>(pk, sk).inspect_err(|_| {
+    pk.fill(0);
+    sk.fill(0);
+  })
+}
+
+/// Pairwise consistency test, signs a fixed message with a freshly 
+/// generated key and checks it verifies under the matching public key.
+#[cfg(feature = "pct")]
+fn spx_pct<P: ParameterSet>(pk: &[u8], sk: &[u8]) -> Result<(), SigError>
+{
+  const PCT_MSG: &[u8] = b"SPHINCS+ pairwise consistency test";
+  // On the heap, a signature is too large for small stacks
+  let sig = &mut vec![0u8; P::BYTES][..];
+  let optrand = spx_deterministic_optrand::<P>(sk);
+  spx_sign_msg::<P>(sig, &mut &PCT_MSG[..], sk, &optrand, Variant::Sphincs)
+    .and_then(|_| spx_verify::<P>(sig, PCT_MSG, pk))
+    .map_err(|_| SigError::PairwiseConsistency)
+}
+
+/// Computes the root node of the top-most subtree from the SK_SEED and 
+/// PUB_SEED of a secret key, ignoring the root stored in it. Only the 
+/// first SPX_N bytes of the result are set.
+pub(crate) fn spx_gen_root<P: ParameterSet>(sk: &[u8]) -> [u8; SPX_MAX_N]
+{
+  let mut ctx = SpxCtx::default();
+  let mut root = [0u8; SPX_MAX_N];
+
+  ctx.sk_seed[..P::N].copy_from_slice(&sk[..P::N]);
+  ctx.pub_seed[..P::N].copy_from_slice(&sk[2*P::N..3*P::N]);
 
   // This hook allows the hash function instantiation to do whatever
   // preparation or computation it needs, based on the public seed.
-  initialize_hash_function(&mut ctx);
+  initialize_hash_function::<P>(&mut ctx);
 
   // Compute root node of the top-most subtree.
-  merkle_gen_root(&mut sk[3*SPX_N..], &ctx);
+  merkle_gen_root::<P>(&mut root, &ctx);
+  root
+}
 
-  pk[SPX_N..2*SPX_N].copy_from_slice(&sk[3*SPX_N..4*SPX_N]);
+/// Selects the SPX_N byte optrand that randomizes R = PRF_msg(SK_PRF, optrand, M).
+/// Randomizing signing can help counter side-channel attacks that would 
+/// benefit from getting a large number of traces when the signer uses the 
+/// same nodes. Only the first SPX_N bytes of the result are set.
+pub(crate) fn spx_optrand<P: ParameterSet>(
+  sk: &[u8], mode: SignMode
+) -> Result<[u8; SPX_MAX_N], SigError>
+{
+  let mut optrand = [0u8; SPX_MAX_N];
+  match mode {
+    SignMode::Deterministic => optrand = spx_deterministic_optrand::<P>(sk),
+    SignMode::Hedged(rng) => randombytes(rng, &mut optrand, P::N),
+    SignMode::External(bytes) => {
+      check_len(bytes, P::N, SigError::OptrandLength)?;
+      optrand[..P::N].copy_from_slice(bytes);
+    }
+  }
+  Ok(optrand)
+}
 
-  return 0; // TODO: Use rust semantics
+/// As in the SPHINCS+ specification and FIPS 205, PUB_SEED takes the 
+/// place of the randomness for deterministic signatures.
+pub(crate) fn spx_deterministic_optrand<P: ParameterSet>(sk: &[u8]) -> [u8; SPX_MAX_N]
+{
+  let mut optrand = [0u8; SPX_MAX_N];
+  optrand[..P::N].copy_from_slice(&sk[2*P::N..3*P::N]);
+  optrand
 }
 
-/// Generates an SPX key pair.
-/// Format sk: [SK_SEED || SK_PRF || PUB_SEED || root]
-/// Format pk: [PUB_SEED || root]
-pub fn  crypto_sign_keypair(
-  pk: &mut[u8], sk: &mut[u8], seed: Option<&[u8]>
-) -> i32
+/// Signing on correctly sized buffers, see [`crypto_sign_signature`]. The 
+/// message is absorbed in parts, see [`Message`].
+/// 
+/// With the `hardened` feature the signature is verified against the public 
+/// key in sk before it is released. On any fault sig is zeroed and 
+/// [`SigError::FaultDetected`] returned.
+pub(crate) fn spx_sign_msg<P: ParameterSet>(
+  sig: &mut[u8], m: &mut dyn Message, sk: &[u8], optrand: &[u8], 
+  variant: Variant
+) -> Result<(), SigError>
 {
-  if let Some(deterministic) = seed {
-    crypto_sign_seed_keypair(pk, sk, deterministic);
-  } else {
-    let mut seed = [0u8; CRYPTO_SEEDBYTES];
-    randombytes(&mut seed, CRYPTO_SEEDBYTES);
-    crypto_sign_seed_keypair(pk, sk, &seed);
+  let res = spx_sign_layers::<P>(sig, m, sk, optrand, variant);
+
+  #[cfg(feature = "hardened")]
+  let res = res.and_then(|_| {
+    match spx_verify_msg::<P>(sig, m, &sk[2*P::N..4*P::N], variant) {
+      Err(SigError::Verify) => Err(SigError::FaultDetected),
+      res => res
+    }
+  });
+
+  if res.is_err() {
+    sig.fill(0);
   }
-  return 0; // TODO: Use rust semantics
+  res
 }
 
-/// Returns an array containing a detached signature.
-pub fn  crypto_sign_signature(
-  sig: &mut[u8], m: &[u8], sk: &[u8], seed: Option<&[u8]>
-)
+fn spx_sign_layers<P: ParameterSet>(
+  sig: &mut[u8], m: &mut dyn Message, sk: &[u8], optrand: &[u8], 
+  variant: Variant
+) -> Result<(), SigError>
 {
-  let mut ctx = SpxCtx::default();
+  let mut ctx = SpxCtx { variant, ..SpxCtx::default() };
 
-  let mut sk_prf = [0u8; SPX_N];
-  sk_prf.copy_from_slice(&sk[SPX_N..SPX_N*2]);
-  let mut pk = [0u8; SPX_N * 2];
-  pk[..SPX_N*2].copy_from_slice(&sk[SPX_N*2..SPX_N*4]);
+  let sk_prf = &sk[P::N..P::N*2];
+  let pk = &sk[P::N*2..P::N*4];
 
-  let mut optrand = [0u8; SPX_N];
-  let mut mhash = [0u8; SPX_FORS_MSG_BYTES];
-  let mut root = [0u8; SPX_N];
+  let mut mhash = [0u8; SPX_MAX_FORS_MSG_BYTES];
+  let mut root = [0u8; SPX_MAX_N];
 
   let mut tree = 0u64;
   let mut idx_leaf =  0u32;
@@ -79,134 +289,370 @@ pub fn  crypto_sign_signature(
 
   let mut idx = 0usize;
 
-  ctx.sk_seed.copy_from_slice(&sk[..SPX_N]);
-  ctx.pub_seed.copy_from_slice(&pk[..SPX_N]);
+  ctx.sk_seed[..P::N].copy_from_slice(&sk[..P::N]);
+  ctx.pub_seed[..P::N].copy_from_slice(&pk[..P::N]);
 
   // This hook allows the hash function instantiation to do whatever
   // preparation or computation it needs, based on the public seed.
-  initialize_hash_function(&mut ctx);
+  initialize_hash_function::<P>(&mut ctx);
 
-  set_type(&mut wots_addr, SPX_ADDR_TYPE_WOTS);
-  set_type(&mut tree_addr, SPX_ADDR_TYPE_HASHTREE);
+  set_type::<P>(&mut wots_addr, SPX_ADDR_TYPE_WOTS);
+  set_type::<P>(&mut tree_addr, SPX_ADDR_TYPE_HASHTREE);
 
-  // Optionally, signing can be made non-deterministic using optrand.
-  // This can help counter side-channel attacks that would benefit from
-  // getting a large number of traces when the signer uses the same nodes.
-  match seed {
-    Some(deterministic) => optrand.copy_from_slice(&deterministic),
-    None => randombytes(&mut optrand, SPX_N)
-  }
-  
   // Compute the digest randomization value.
-  gen_message_random(sig, &sk_prf, &optrand, m, m.len(), &ctx);
+  let mut prf = PrfMsg::new::<P>(sk_prf, &optrand[..P::N], &ctx);
+  m.absorb(&mut |part| prf.update::<P>(part, &ctx))?;
+  prf.finalize::<P>(sig, &ctx);
 
   // Derive the message digest and leaf index from R, PK and M.
-  hash_message(
-    &mut mhash, &mut tree, &mut idx_leaf, sig, &pk, m, m.len(), &ctx
-  );
-  idx += SPX_N;
+  let mut hmsg = HashMsg::new::<P>(sig, pk, &ctx);
+  m.absorb(&mut |part| hmsg.update::<P>(part, &ctx))?;
+  hmsg.finalize::<P>(&mut mhash, &mut tree, &mut idx_leaf, &ctx);
+  idx += P::N;
 
-  set_tree_addr(&mut wots_addr, tree);
-  set_keypair_addr(&mut wots_addr, idx_leaf);
+  set_tree_addr::<P>(&mut wots_addr, tree);
+  set_keypair_addr::<P>(&mut wots_addr, idx_leaf);
 
   // Sign the message hash using FORS.
-  fors_sign(&mut sig[idx..], &mut root, &mhash, &ctx, &mut wots_addr);
-  idx += SPX_FORS_BYTES;
+  fors_sign::<P>(&mut sig[idx..], &mut root, &mhash, &ctx, &mut wots_addr);
+  idx += P::FORS_BYTES;
+
+  for i in 0..P::D  {
+      set_layer_addr::<P>(&mut tree_addr, i as u32);
+      set_tree_addr::<P>(&mut tree_addr, tree);
 
-  for i in 0..SPX_D  {
-      set_layer_addr(&mut tree_addr, i as u32);
-      set_tree_addr(&mut tree_addr, tree);
+      copy_subtree_addr::<P>(&mut wots_addr, &mut tree_addr);
+      set_keypair_addr::<P>(&mut wots_addr, idx_leaf);
 
-      copy_subtree_addr(&mut wots_addr, &mut tree_addr);
-      set_keypair_addr(&mut wots_addr, idx_leaf);
+      #[cfg(feature = "hardened")]
+      let signed = root;
 
-      merkle_sign(
+      merkle_sign::<P>(
         &mut sig[idx..], &mut root, &ctx, 
         &mut wots_addr, &mut tree_addr, idx_leaf
       );
-      idx += SPX_WOTS_BYTES + SPX_TREE_HEIGHT * SPX_N;
+
+      // A faulty WOTS signature or subtree would let an attacker graft 
+      // their own subtree, so recompute this layer from the signature.
+      #[cfg(feature = "hardened")]
+      spx_check_layer::<P>(
+        &sig[idx..], &signed, &root, &ctx, &wots_addr, &tree_addr, idx_leaf
+      )?;
+
+      // A fault on the authentication path changes the root consistently,
+      // the layer above would sign it and the signature still verifies. 
+      // Compute the subtree a second time before it is signed.
+      #[cfg(feature = "hardened")]
+      if i + 1 < P::D {
+        spx_check_root::<P>(&root, &ctx, i as u32, tree)?;
+      }
+      idx += P::WOTS_BYTES + P::TREE_HEIGHT * P::N;
 
       // Update the indices for the next layer.
-      idx_leaf = (tree & ((1 << SPX_TREE_HEIGHT)-1)) as u32;
-      tree = tree >> SPX_TREE_HEIGHT;
+      idx_leaf = (tree & ((1 << P::TREE_HEIGHT)-1)) as u32;
+      tree >>= P::TREE_HEIGHT;
   }
+
+  Ok(())
 }
 
-/// Verifies a detached signature and message under a given public key.
-pub fn crypto_sign_verify(sig: &[u8], msg: &[u8], pk: &[u8]) -> Result<(), SigError>
+/// Recomputes the subtree root from a freshly generated WOTS signature of 
+/// msg and its authentication path, then compares it to the root returned 
+/// by merkle_sign.
+#[cfg(feature = "hardened")]
+fn spx_check_layer<P: ParameterSet>(
+  sig: &[u8], msg: &[u8], root: &[u8], ctx: &SpxCtx,
+  wots_addr: &[u32; 8], tree_addr: &[u32; 8], idx_leaf: u32
+) -> Result<(), SigError>
 {
-  if sig.len() != SPX_BYTES {
-    return Err(SigError::Input)
-  }
-  let mut ctx = SpxCtx::default();
-  let pub_root: &[u8] = &pk[SPX_N..];
-  let mut mhash = [0u8; SPX_FORS_MSG_BYTES];
-  let mut wots_pk = [0u8; SPX_WOTS_BYTES];
-  let (mut root, mut leaf) = ([0u8; SPX_N], [0u8; SPX_N]);
-  let mut tree = 0u64;
-  let mut idx_leaf =  0u32;
-  let (mut wots_addr, mut tree_addr, mut wots_pk_addr) = ([0u32; 8], [0u32; 8], [0u32; 8]);
-  let mut idx = 0usize;
-  
-  ctx.pub_seed[..].copy_from_slice(&pk[..SPX_N]);
+  use subtle::ConstantTimeEq;
 
-  // This hook allows the hash function instantiation to do whatever
-  // preparation or computation it needs, based on the public seed.
-  initialize_hash_function(&mut ctx);
+  let mut wots_pk = [0u8; SPX_MAX_WOTS_BYTES];
+  let (mut leaf, mut check) = ([0u8; SPX_MAX_N], [0u8; SPX_MAX_N]);
+  let (mut wots_addr, mut tree_addr) = (*wots_addr, *tree_addr);
+  let mut wots_pk_addr = [0u32; 8];
 
-  set_type(&mut wots_addr, SPX_ADDR_TYPE_WOTS);
-  set_type(&mut tree_addr, SPX_ADDR_TYPE_HASHTREE);
-  set_type(&mut wots_pk_addr, SPX_ADDR_TYPE_WOTSPK);
+  set_type::<P>(&mut wots_pk_addr, SPX_ADDR_TYPE_WOTSPK);
+  copy_keypair_addr::<P>(&mut wots_pk_addr, &wots_addr);
 
-  // Derive the message digest and leaf index from R || PK || M.
-  // The additional SPX_N is a result of the hash domain separator.
-  hash_message(
-    &mut mhash, &mut tree, &mut idx_leaf, sig, 
-    pk, &msg, msg.len(), &ctx
+  wots_pk_from_sig::<P>(&mut wots_pk, sig, msg, ctx, &mut wots_addr);
+  thash::<P>(&mut leaf, Some(&wots_pk), P::WOTS_LEN, ctx, &wots_pk_addr);
+  compute_root::<P>(
+    &mut check, &leaf, idx_leaf, 0, &sig[P::WOTS_BYTES..], 
+    P::TREE_HEIGHT as u32, ctx, &mut tree_addr
   );
-  idx += SPX_N;
 
-  // Layer correctly defaults to 0, so no need to set_layer_addr
-  set_tree_addr(&mut wots_addr, tree);
-  set_keypair_addr(&mut wots_addr, idx_leaf);
+  if bool::from(check[..P::N].ct_eq(&root[..P::N])) {
+    Ok(())
+  } else {
+    Err(SigError::FaultDetected)
+  }
+}
+
+/// Recomputes the subtree root at layer and tree with a separate treehash 
+/// and compares it to the root returned by merkle_sign.
+#[cfg(feature = "hardened")]
+fn spx_check_root<P: ParameterSet>(
+  root: &[u8], ctx: &SpxCtx, layer: u32, tree: u64
+) -> Result<(), SigError>
+{
+  use subtle::ConstantTimeEq;
 
-  fors_pk_from_sig(&mut root, &sig[idx..], &mhash, &ctx, &mut wots_addr);
-  idx += SPX_FORS_BYTES;
+  let mut check = [0u8; SPX_MAX_N];
+  merkle_subtree_root::<P>(&mut check, ctx, layer, tree);
+
+  if bool::from(check[..P::N].ct_eq(&root[..P::N])) {
+    Ok(())
+  } else {
+    Err(SigError::FaultDetected)
+  }
+}
 
-  // For each subtree..
-  for i in 0..SPX_D  {
-    set_layer_addr(&mut tree_addr, i as u32);
-    set_tree_addr(&mut tree_addr, tree);
-    copy_subtree_addr(&mut wots_addr, &mut tree_addr);
-    set_keypair_addr(&mut wots_addr, idx_leaf);
+/// The FIPS 205 domain separator, 0 for pure and 1 for pre-hash messages, 
+/// followed by the context length. The context string is at most 255 bytes.
+#[cfg(any(feature = "slh-dsa", feature = "prehash"))]
+pub(crate) fn slh_dsa_header(domain: u8, ctx: &[u8]) -> Result<[u8; 2], SigError>
+{
+  let ctx_len = u8::try_from(ctx.len()).map_err(|_| SigError::ContextLength)?;
+  Ok([domain, ctx_len])
+}
 
-    copy_keypair_addr(&mut wots_pk_addr, &mut wots_addr);
+/// Verification on correctly sized buffers, see [`crypto_sign_verify`]
+pub(crate) fn spx_verify<P: ParameterSet>(
+  sig: &[u8], mut msg: &[u8], pk: &[u8]
+) -> Result<(), SigError>
+{
+  spx_verify_msg::<P>(sig, &mut msg, pk, Variant::Sphincs)
+}
 
-    // The WOTS public key is only correct if the signature was correct.
-    // Initially, root is the FORS pk, but on subsequent iterations it is
-    // the root of the subtree below the currently processed subtree.
-    wots_pk_from_sig(&mut wots_pk, &sig[idx..], &root, &ctx, &mut wots_addr);
-    idx += SPX_WOTS_BYTES;
+/// Verification of a message that is absorbed in parts, see [`Message`]
+pub(crate) fn spx_verify_msg<P: ParameterSet>(
+  sig: &[u8], msg: &mut dyn Message, pk: &[u8], variant: Variant
+) -> Result<(), SigError>
+{
+  let mut verifier = SpxVerifier::<P>::new(sig, pk, variant);
+  msg.absorb(&mut |part| verifier.update(part))?;
+  verifier.finalize()
+}
 
-    // Compute the leaf node using the WOTS public key.
-    thash::<SPX_WOTS_LEN>(&mut leaf, Some(&wots_pk), &ctx, &wots_pk_addr);
+/// Incremental verification, R is the first SPX_N bytes of the signature so 
+/// the message digest can be computed while the message arrives.
+pub(crate) struct SpxVerifier<'a, P: ParameterSet> {
+  sig: &'a [u8],
+  pk: [u8; 2*SPX_MAX_N],
+  ctx: SpxCtx,
+  hmsg: HashMsg,
+  params: PhantomData<P>
+}
 
-    // Compute the root node of this subtree.
-    compute_root(
-      &mut root, &leaf, idx_leaf, 0, &sig[idx..], 
-      SPX_TREE_HEIGHT as u32, &ctx, &mut tree_addr
-    );
-    idx += SPX_TREE_HEIGHT * SPX_N;
+impl<'a, P: ParameterSet> SpxVerifier<'a, P> {
+  pub(crate) fn new(sig: &'a [u8], pk: &[u8], variant: Variant) -> Self
+  {
+    let mut ctx = SpxCtx { variant, ..SpxCtx::default() };
+    ctx.pub_seed[..P::N].copy_from_slice(&pk[..P::N]);
+
+    // This hook allows the hash function instantiation to do whatever
+    // preparation or computation it needs, based on the public seed.
+    initialize_hash_function::<P>(&mut ctx);
+
+    // Derive the message digest and leaf index from R || PK || M.
+    // The additional SPX_N is a result of the hash domain separator.
+    let hmsg = HashMsg::new::<P>(sig, pk, &ctx);
+    let mut pk_bytes = [0u8; 2*SPX_MAX_N];
+    pk_bytes[..2*P::N].copy_from_slice(&pk[..2*P::N]);
+    Self { sig, pk: pk_bytes, ctx, hmsg, params: PhantomData }
+  }
 
-    // Update the indices for the next layer.
-    idx_leaf = (tree & ((1 << SPX_TREE_HEIGHT)-1)) as u32;
-    tree = tree >> SPX_TREE_HEIGHT;
+  pub(crate) fn update(&mut self, msg: &[u8])
+  {
+    self.hmsg.update::<P>(msg, &self.ctx);
   }
 
-  // Check if the root node equals the root node in the public key.
-  if root != pub_root {
-    return Err(SigError::Verify);
+  pub(crate) fn finalize(self) -> Result<(), SigError>
+  {
+    let Self { sig, pk, ctx, hmsg, .. } = self;
+    let pub_root: &[u8] = &pk[P::N..2*P::N];
+    let mut mhash = [0u8; SPX_MAX_FORS_MSG_BYTES];
+    let mut wots_pk = [0u8; SPX_MAX_WOTS_BYTES];
+    let (mut root, mut leaf) = ([0u8; SPX_MAX_N], [0u8; SPX_MAX_N]);
+    let mut tree = 0u64;
+    let mut idx_leaf =  0u32;
+    let (mut wots_addr, mut tree_addr, mut wots_pk_addr) = ([0u32; 8], [0u32; 8], [0u32; 8]);
+    let mut idx = 0usize;
+
+    set_type::<P>(&mut wots_addr, SPX_ADDR_TYPE_WOTS);
+    set_type::<P>(&mut tree_addr, SPX_ADDR_TYPE_HASHTREE);
+    set_type::<P>(&mut wots_pk_addr, SPX_ADDR_TYPE_WOTSPK);
+
+    hmsg.finalize::<P>(&mut mhash, &mut tree, &mut idx_leaf, &ctx);
+    idx += P::N;
+
+    // Layer correctly defaults to 0, so no need to set_layer_addr
+    set_tree_addr::<P>(&mut wots_addr, tree);
+    set_keypair_addr::<P>(&mut wots_addr, idx_leaf);
+
+    fors_pk_from_sig::<P>(&mut root, &sig[idx..], &mhash, &ctx, &mut wots_addr);
+    idx += P::FORS_BYTES;
+
+    // For each subtree..
+    for i in 0..P::D  {
+      set_layer_addr::<P>(&mut tree_addr, i as u32);
+      set_tree_addr::<P>(&mut tree_addr, tree);
+      copy_subtree_addr::<P>(&mut wots_addr, &mut tree_addr);
+      set_keypair_addr::<P>(&mut wots_addr, idx_leaf);
+
+      copy_keypair_addr::<P>(&mut wots_pk_addr, &wots_addr);
+
+      // The WOTS public key is only correct if the signature was correct.
+      // Initially, root is the FORS pk, but on subsequent iterations it is
+      // the root of the subtree below the currently processed subtree.
+      wots_pk_from_sig::<P>(&mut wots_pk, &sig[idx..], &root, &ctx, &mut wots_addr);
+      idx += P::WOTS_BYTES;
+
+      // Compute the leaf node using the WOTS public key.
+      thash::<P>(&mut leaf, Some(&wots_pk), P::WOTS_LEN, &ctx, &wots_pk_addr);
+
+      // Compute the root node of this subtree.
+      compute_root::<P>(
+        &mut root, &leaf, idx_leaf, 0, &sig[idx..], 
+        P::TREE_HEIGHT as u32, &ctx, &mut tree_addr
+      );
+      idx += P::TREE_HEIGHT * P::N;
+
+      // Update the indices for the next layer.
+      idx_leaf = (tree & ((1 << P::TREE_HEIGHT)-1)) as u32;
+      tree >>= P::TREE_HEIGHT;
+    }
+
+    // Check if the root node equals the root node in the public key.
+    if root[..P::N] != *pub_root {
+      return Err(SigError::Verify);
+    }
+
+    Ok(())
   }
+}
+
+/// A message that is fed to the incremental message hashes in parts. Signing 
+/// absorbs the message twice, once for R and once for the digest.
+pub(crate) trait Message {
+  fn absorb(&mut self, f: &mut dyn FnMut(&[u8])) -> Result<(), SigError>;
+}
 
-  return Ok(());
-}
\ No newline at end of file
+impl Message for &[u8] {
+  fn absorb(&mut self, f: &mut dyn FnMut(&[u8])) -> Result<(), SigError>
+  {
+    f(self);
+    Ok(())
+  }
+}
+
+/// A message made of several consecutive parts, such as a domain separator 
+/// prefix followed by the message itself
+impl Message for &[&[u8]] {
+  fn absorb(&mut self, f: &mut dyn FnMut(&[u8])) -> Result<(), SigError>
+  {
+    self.iter().for_each(|part| f(part));
+    Ok(())
+  }
+}
+
+/// Reads the message to the end in chunks, it can only be absorbed once
+#[cfg(feature = "std")]
+pub(crate) struct ReadMessage<'a, R: std::io::Read>(pub &'a mut R);
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Message for ReadMessage<'_, R> {
+  fn absorb(&mut self, f: &mut dyn FnMut(&[u8])) -> Result<(), SigError>
+  {
+    let mut buf = [0u8; 4096];
+    loop {
+      match self.0.read(&mut buf) {
+        Ok(0) => return Ok(()),
+        Ok(n) => f(&buf[..n]),
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(SigError::Io(e.kind()))
+      }
+    }
+  }
+}
+
+/// Rewinds to where the message started before every pass
+#[cfg(feature = "std")]
+pub(crate) struct SeekMessage<'a, R: std::io::Read + std::io::Seek> {
+  reader: &'a mut R,
+  start: u64
+}
+
+#[cfg(feature = "std")]
+impl<'a, R: std::io::Read + std::io::Seek> SeekMessage<'a, R> {
+  /// The message runs from the current position to the end of the reader
+  pub(crate) fn new(reader: &'a mut R) -> Result<Self, SigError>
+  {
+    let start = reader.stream_position().map_err(|e| SigError::Io(e.kind()))?;
+    Ok(Self { reader, start })
+  }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + std::io::Seek> Message for SeekMessage<'_, R> {
+  fn absorb(&mut self, f: &mut dyn FnMut(&[u8])) -> Result<(), SigError>
+  {
+    self.reader.seek(std::io::SeekFrom::Start(self.start))
+      .map_err(|e| SigError::Io(e.kind()))?;
+    ReadMessage(&mut *self.reader).absorb(f)
+  }
+}
+
+#[cfg(all(test, any(feature = "pct", feature = "hardened")))]
+mod tests {
+  use super::*;
+  use crate::params::Selected;
+
+  #[test]
+  #[cfg(feature = "pct")]
+  fn pct_failure_zeroizes_keys() {
+    let mut pk = [0u8; Selected::PK_BYTES];
+    let mut sk = [0u8; Selected::SK_BYTES];
+    let seed = [7u8; 3*Selected::N];
+    spx_seed_keypair::<Selected>(&mut pk, &mut sk, &seed).unwrap();
+
+    // A fault in SK_SEED after the root was computed
+    sk[0] ^= 1;
+    assert_eq!(
+      spx_pct_or_zeroize::<Selected>(&mut pk, &mut sk), 
+      Err(SigError::PairwiseConsistency)
+    );
+    assert!(pk.iter().all(|&b| b == 0));
+    assert!(sk.iter().all(|&b| b == 0));
+  }
+
+  #[test]
+  #[cfg(feature = "hardened")]
+  fn hardened_subtree_fault() {
+    let mut pk = [0u8; Selected::PK_BYTES];
+    let mut sk = [0u8; Selected::SK_BYTES];
+    let mut sig = [0u8; Selected::BYTES];
+    let seed = [9u8; 3*Selected::N];
+    let optrand = [0u8; Selected::N];
+    let msg = [1u8; 32];
+    spx_seed_keypair::<Selected>(&mut pk, &mut sk, &seed).unwrap();
+
+    // Flips an authentication path node of the bottom subtree, its root 
+    // and the WOTS signature above it stay consistent with each other
+    crate::utilsx1::fault::arm();
+    assert_eq!(
+      spx_sign_msg::<Selected>(
+        &mut sig, &mut &msg[..], &sk, &optrand, Variant::Sphincs
+      ),
+      Err(SigError::FaultDetected)
+    );
+    assert!(sig.iter().all(|&b| b == 0));
+
+    // Without a fault the same signature is produced and verifies
+    spx_sign_msg::<Selected>(
+      &mut sig, &mut &msg[..], &sk, &optrand, Variant::Sphincs
+    ).unwrap();
+    assert!(spx_verify::<Selected>(&sig, &msg, &pk).is_ok());
+  }
+}
@@ -1,31 +1,55 @@
-#[cfg(all(feature = "haraka", feature = "robust"))]
 mod haraka_robust;
-#[cfg(all(feature = "haraka", feature = "simple"))]
 mod haraka_simple;
-#[cfg(all(feature = "sha2", feature = "robust"))]
 mod sha2_robust;
-#[cfg(all(feature = "sha2", feature = "simple"))]
 mod sha2_simple;
-#[cfg(all(feature = "shake", feature = "robust"))]
 mod shake_robust;
-#[cfg(all(feature = "shake", feature = "simple"))]
 mod shake_simple;
 
+use crate::context::SpxCtx;
+use crate::params::*;
 
-#[cfg(all(feature = "haraka", feature = "simple"))]
-pub use haraka_simple::*; 
+// Buffer sizes for the prefixed input, F and H take at most two blocks and
+// dominate the running time so they avoid zeroing the largest buffer
+const SMALL: usize = SPX_MAX_N + SPX_ADDR_BYTES + 2 * SPX_MAX_N;
+const LARGE: usize = SPX_MAX_N + SPX_ADDR_BYTES + SPX_MAX_THASH_BLOCKS * SPX_MAX_N;
 
-#[cfg(all(feature = "haraka", feature = "robust"))]
-pub use haraka_robust::*; 
+/// Tweakable hash of inblocks concatenated blocks of SPX_N bytes. When 
+/// input is None the blocks are read from out, which is overwritten with 
+/// the SPX_N byte result.
+///
+/// F and H are called with a literal block count, inlining down to the
+/// compression function folds it into a constant length. Without it the 
+/// hash chain crosses codegen units and the SHA-2 sets run almost twice as 
+/// slow.
+#[inline]
+pub fn thash<P: ParameterSet>(
+  out: &mut[u8], input: Option<&[u8]>, inblocks: usize, ctx: &SpxCtx, addr: &[u32]
+)
+{
+  if inblocks <= 2 {
+    thash_buf::<P, SMALL>(out, input, inblocks, ctx, addr)
+  } else {
+    thash_buf::<P, LARGE>(out, input, inblocks, ctx, addr)
+  }
+}
 
-#[cfg(all(feature = "sha2", feature = "simple"))]
-pub use sha2_simple::*; 
-
-#[cfg(all(feature = "sha2", feature = "robust"))]
-pub use sha2_robust::*; 
-
-#[cfg(all(feature = "shake", feature = "simple"))]
-pub use shake_simple::*; 
-
-#[cfg(all(feature = "shake", feature = "robust"))]
-pub use shake_robust::*; 
\ No newline at end of file
+#[inline]
+fn thash_buf<P: ParameterSet, const B: usize>(
+  out: &mut[u8], input: Option<&[u8]>, inblocks: usize, ctx: &SpxCtx, addr: &[u32]
+)
+{
+  match (P::HASH, P::THASH) {
+    (HashFamily::Haraka, Thash::Robust) => 
+      haraka_robust::thash::<P, B>(out, input, inblocks, ctx, addr),
+    (HashFamily::Haraka, Thash::Simple) => 
+      haraka_simple::thash::<P, B>(out, input, inblocks, ctx, addr),
+    (HashFamily::Sha2, Thash::Robust) => 
+      sha2_robust::thash::<P, B>(out, input, inblocks, ctx, addr),
+    (HashFamily::Sha2, Thash::Simple) => 
+      sha2_simple::thash::<P, B>(out, input, inblocks, ctx, addr),
+    (HashFamily::Shake, Thash::Robust) => 
+      shake_robust::thash::<P>(out, input, inblocks, ctx, addr),
+    (HashFamily::Shake, Thash::Simple) => 
+      shake_simple::thash::<P>(out, input, inblocks, ctx, addr),
+  }
+}
@@ -5,67 +5,69 @@ use crate::{
   context::SpxCtx
 };
 
-/// Takes an array of inblocks concatenated arrays of SPX_N bytes.
-pub fn thash<const N: usize>(
-  out: &mut[u8], input: Option<&[u8]>, ctx: &SpxCtx, addr: &[u32]
+/// Takes an array of inblocks concatenated arrays of SPX_N bytes, the 
+/// buffers hold B bytes.
+#[inline]
+pub fn thash<P: ParameterSet, const B: usize>(
+  out: &mut[u8], input: Option<&[u8]>, inblocks: usize, ctx: &SpxCtx, addr: &[u32]
 )
-  where [(); SPX_N + SPX_SHA256_ADDR_BYTES + N * SPX_N]: Sized
 {
-  #[cfg(all(feature="sha2", not(any(feature="f128", feature="s128"))))]
-  {
-    if N > 1 {
-      thash_512::<N>(out, input, ctx, addr);
-      return;
-    }
+  if P::SHA512 && inblocks > 1 {
+    thash_512::<P, B>(out, input, inblocks, ctx, addr);
+    return;
   }
+  let inlen = inblocks * P::N;
   let mut outbuf = [0u8; SPX_SHA256_OUTPUT_BYTES];
-  let mut buf = [0u8; SPX_N + SPX_SHA256_ADDR_BYTES + N * SPX_N];
-  let mut bitmask = [0u8; N * SPX_N];
+  let mut buf = [0u8; B];
+  let mut bitmask = [0u8; B];
   let mut sha2_state = [0u8; 40]
 ;
-  buf[..SPX_N].copy_from_slice(&ctx.pub_seed);
-  buf[SPX_N..SPX_N + SPX_SHA256_ADDR_BYTES]
+  buf[..P::N].copy_from_slice(&ctx.pub_seed[..P::N]);
+  buf[P::N..P::N + SPX_SHA256_ADDR_BYTES]
     .copy_from_slice(&address_to_bytes(addr)[..SPX_SHA256_ADDR_BYTES]);
-  mgf1_256(&mut bitmask, N * SPX_N, &buf);
+  mgf1_256(&mut bitmask, inlen, &buf[..P::N + SPX_SHA256_ADDR_BYTES]);
 
   // Retrieve precomputed state containing pub_seed
   sha2_state.copy_from_slice(&ctx.state_seeded[..40]);
 
-  for i in 0..N * SPX_N {
-      buf[SPX_N + SPX_SHA256_ADDR_BYTES + i] = input.unwrap_or(out)[i] ^ bitmask[i];
+  let input = &input.unwrap_or(out)[..inlen];
+  let masked = &mut buf[P::N + SPX_SHA256_ADDR_BYTES..][..inlen];
+  for ((b, x), m) in masked.iter_mut().zip(input).zip(&bitmask[..inlen]) {
+    *b = x ^ m;
   }
 
   sha256_inc_finalize(
-    &mut outbuf, &mut sha2_state, &buf[SPX_N..], SPX_SHA256_ADDR_BYTES + N*SPX_N);
-  out[..SPX_N].copy_from_slice(&outbuf[..SPX_N]);
+    &mut outbuf, &mut sha2_state, &buf[P::N..], SPX_SHA256_ADDR_BYTES + inlen);
+  out[..P::N].copy_from_slice(&outbuf[..P::N]);
 }
 
-#[cfg(all(feature="sha2", not(any(feature="f128", feature="s128"))))]
-pub fn thash_512<const N: usize>(
-  out: &mut[u8], input: Option<&[u8]>, ctx: &SpxCtx, addr: &[u32]
+#[inline]
+pub fn thash_512<P: ParameterSet, const B: usize>(
+  out: &mut[u8], input: Option<&[u8]>, inblocks: usize, ctx: &SpxCtx, addr: &[u32]
 )
-  where [(); SPX_N + SPX_SHA256_ADDR_BYTES + N * SPX_N]: Sized
 {
+  let inlen = inblocks * P::N;
   let mut outbuf = [0u8; SPX_SHA512_OUTPUT_BYTES];
-  let mut bitmask = [0u8; N * SPX_N];
-  let mut buf = [0u8; SPX_N + SPX_SHA256_ADDR_BYTES + N * SPX_N];
+  let mut bitmask = [0u8; B];
+  let mut buf = [0u8; B];
   let mut sha2_state = [0u8; 72];
 
-  buf[..SPX_N].copy_from_slice(&ctx.pub_seed);
-  buf[SPX_N..SPX_N + SPX_SHA256_ADDR_BYTES]
+  buf[..P::N].copy_from_slice(&ctx.pub_seed[..P::N]);
+  buf[P::N..P::N + SPX_SHA256_ADDR_BYTES]
     .copy_from_slice(&address_to_bytes(addr)[..SPX_SHA256_ADDR_BYTES]);
-  mgf1_512(&mut bitmask, N * SPX_N, &buf);
+  mgf1_512(&mut bitmask, inlen, &buf[..P::N + SPX_SHA256_ADDR_BYTES]);
 
   // Retrieve precomputed state containing pub_seed
   sha2_state[..72].copy_from_slice(&ctx.state_seeded_512);
 
-  // TODO: copy from slice
-  for i in 0..N * SPX_N {
-      buf[SPX_N + SPX_SHA256_ADDR_BYTES + i] = input.unwrap_or(out)[i] ^ bitmask[i];
+  let input = &input.unwrap_or(out)[..inlen];
+  let masked = &mut buf[P::N + SPX_SHA256_ADDR_BYTES..][..inlen];
+  for ((b, x), m) in masked.iter_mut().zip(input).zip(&bitmask[..inlen]) {
+    *b = x ^ m;
   }
 
   sha512_inc_finalize(
-    &mut outbuf, &mut sha2_state, &buf[SPX_N..], SPX_SHA256_ADDR_BYTES + N*SPX_N
+    &mut outbuf, &mut sha2_state, &buf[P::N..], SPX_SHA256_ADDR_BYTES + inlen
   );
-  out[..SPX_N].copy_from_slice(&outbuf[..SPX_N]);
-}
\ No newline at end of file
+  out[..P::N].copy_from_slice(&outbuf[..P::N]);
+}
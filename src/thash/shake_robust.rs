@@ -6,29 +6,30 @@ use crate::{
 use sha3::{Shake256, digest::{Update, ExtendableOutput, XofReader}};
 
 /// Takes an array of inblocks concatenated arrays of SPX_N bytes.
-pub fn thash<const N: usize>(
-  out: &mut[u8], input: Option<&[u8]>,  ctx: &SpxCtx, addr: &[u32]
+pub fn thash<P: ParameterSet>(
+  out: &mut[u8], input: Option<&[u8]>, inblocks: usize, ctx: &SpxCtx, addr: &[u32]
 )
-  where [(); SPX_ADDR_BYTES + (N + 1) * SPX_N]:, [(); N * SPX_N]: Sized
 {
-  const N_PLUS_ADDR: usize = SPX_N + SPX_ADDR_BYTES; 
-  let mut buf = [0u8; SPX_ADDR_BYTES + (N + 1) * SPX_N];
-  let mut bitmask = [0u8; N * SPX_N];
-  
-  buf[..SPX_N].copy_from_slice(&ctx.pub_seed);
-  buf[SPX_N..SPX_N+32].copy_from_slice(&address_to_bytes(addr));
+  let inlen = inblocks * P::N;
+  let addr_bytes = address_to_bytes(addr);
+  let mut bitmask = [0u8; SPX_MAX_N];
 
   let mut hasher = Shake256::default();
-  hasher.update(&buf[..N_PLUS_ADDR]); 
-  let mut reader = hasher.finalize_xof();
-  reader.read(&mut bitmask[..N * SPX_N]);
+  hasher.update(&ctx.pub_seed[..P::N]);
+  hasher.update(&addr_bytes);
+  let mut mask_reader = hasher.finalize_xof();
 
-  for i in 0..N * SPX_N {
-    buf[N_PLUS_ADDR + i] = input.unwrap_or(out)[i] ^ bitmask[i];
+  // The bitmask is squeezed and applied one block at a time
+  let mut hasher = Shake256::default();
+  hasher.update(&ctx.pub_seed[..P::N]);
+  hasher.update(&addr_bytes);
+  for block in input.unwrap_or(out)[..inlen].chunks(P::N) {
+    mask_reader.read(&mut bitmask[..P::N]);
+    for (mask, byte) in bitmask.iter_mut().zip(block) {
+      *mask ^= byte;
+    }
+    hasher.update(&bitmask[..P::N]);
   }
-
-  hasher = Shake256::default();
-  hasher.update(&buf[..N_PLUS_ADDR + N*SPX_N]);
   let mut reader = hasher.finalize_xof();
-  reader.read(&mut out[..SPX_N]);
-}
\ No newline at end of file
+  reader.read(&mut out[..P::N]);
+}
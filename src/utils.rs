@@ -9,10 +9,11 @@ pub fn ull_to_bytes(out: &mut[u8], outlen: usize, mut input: u64)
   // Iterate over out in decreasing order, for big-endianness.
   for i in (0..outlen).rev() {
     out[i] = (input & 0xff) as u8;
-    input = input >> 8;
+    input >>= 8;
   }
 }
 
+#[inline]
 pub fn u32_to_bytes(out: &mut[u8], input: u32)
 {
   out[0] = (input >> 24) as u8;
@@ -25,62 +26,64 @@ pub fn u32_to_bytes(out: &mut[u8], input: u32)
 pub fn bytes_to_ull(input: &[u8], inlen: usize ) -> u64
 {
   let mut retval = 0u64;
-  for i in 0..inlen  {
-    retval |= (input[i] as u64) << (8*(inlen - 1 - i));
+  for (i, byte) in input[..inlen].iter().enumerate()  {
+    retval |= (*byte as u64) << (8*(inlen - 1 - i));
   }
-  return retval;
+  retval
 }
 
 /// Computes a root node given a leaf and an auth path.
 /// Expects address to be complete other than the tree_height and tree_index.
-pub fn compute_root(
+#[allow(clippy::too_many_arguments)]
+pub fn compute_root<P: ParameterSet>(
   root: &mut[u8], leaf: &[u8], mut leaf_idx: u32, mut idx_offset: u32,
   auth_path: &[u8], tree_height: u32, ctx: &SpxCtx, addr: &mut[u32; 8]
 )
 {
-  let mut buffer = [0u8; 2 * SPX_N];
+  let mut buffer = [0u8; 2 * SPX_MAX_N];
   let mut idx = 0usize;
 
   // If leaf_idx is odd (last bit = 1), current path element is a right child
   // and auth_path has to go left. Otherwise it is the other way around.
   if (leaf_idx & 1) != 0 {
-    buffer[SPX_N..].copy_from_slice(&leaf[..SPX_N]);
-    buffer[..SPX_N].copy_from_slice(&auth_path[..SPX_N]);
+    buffer[P::N..2*P::N].copy_from_slice(&leaf[..P::N]);
+    buffer[..P::N].copy_from_slice(&auth_path[..P::N]);
   }
   else {
-    buffer[..SPX_N].copy_from_slice(&leaf[..SPX_N]);
-    buffer[SPX_N..].copy_from_slice(&auth_path[..SPX_N]);
+    buffer[..P::N].copy_from_slice(&leaf[..P::N]);
+    buffer[P::N..2*P::N].copy_from_slice(&auth_path[..P::N]);
   }
-  idx += SPX_N;
+  idx += P::N;
 
   for i in 0..(tree_height - 1) {
     leaf_idx >>= 1;
     idx_offset >>= 1;
     // Set the address of the node we're creating.
-    set_tree_height(addr, i + 1);
-    set_tree_index(addr, leaf_idx + idx_offset);
+    set_tree_height::<P>(addr, i + 1);
+    set_tree_index::<P>(addr, leaf_idx + idx_offset);
 
     // Pick the right or left neighbour, depending on parity of the node.
     if (leaf_idx & 1) != 0 {
-      let tmp_buffer = buffer.clone();
-      thash::<2>(&mut buffer[SPX_N..], Some(&tmp_buffer), ctx, addr);
-      buffer[..SPX_N].copy_from_slice(&auth_path[idx..][..SPX_N]);
+      let tmp_buffer = buffer;
+      thash::<P>(&mut buffer[P::N..], Some(&tmp_buffer), 2, ctx, addr);
+      buffer[..P::N].copy_from_slice(&auth_path[idx..][..P::N]);
   }
     else {
-      thash::<2>(&mut buffer, None, ctx, addr);
-      buffer[SPX_N..].copy_from_slice(&auth_path[idx..][..SPX_N]);
+      thash::<P>(&mut buffer, None, 2, ctx, addr);
+      buffer[P::N..2*P::N].copy_from_slice(&auth_path[idx..][..P::N]);
     }
-    idx += SPX_N;
+    idx += P::N;
   }
 
   // The last iteration is exceptional; we do not copy an auth_path node.
   leaf_idx >>= 1;
   idx_offset >>= 1;
-  set_tree_height(addr, tree_height);
-  set_tree_index(addr, leaf_idx + idx_offset);
-  thash::<2>(root, Some(&buffer), ctx, addr);
+  set_tree_height::<P>(addr, tree_height);
+  set_tree_index::<P>(addr, leaf_idx + idx_offset);
+  thash::<P>(root, Some(&buffer), 2, ctx, addr);
 }
 
+#[inline]
 pub fn bytes_to_address(addr: &mut[u32], bytes: &[u8; 32])
 {
   for i in 0..8 {
@@ -90,6 +93,7 @@ pub fn bytes_to_address(addr: &mut[u32], bytes: &[u8; 32])
   }
 }
 
+#[inline]
 pub fn address_to_bytes(addr: &[u32]) -> [u8; 32] 
 {
   let mut out = [0u8; 32];
@@ -7,6 +7,25 @@ use crate::wotsx1::{ LeafInfoX1, wots_gen_leafx1 };
 
 // TODO: dedup treehash functions
 
+/// Fault injection for the hardened tests, flips a bit of the next
+/// authentication path node wots_treehashx1 computes on this thread.
+#[cfg(all(test, feature = "hardened"))]
+pub(crate) mod fault {
+  use core::cell::Cell;
+
+  std::thread_local!(static ARMED: Cell<bool> = const { Cell::new(false) });
+
+  pub(crate) fn arm() {
+    ARMED.with(|armed| armed.set(true));
+  }
+
+  pub(crate) fn inject(node: &mut [u8]) {
+    if ARMED.with(|armed| armed.replace(false)) {
+      node[0] ^= 1;
+    }
+  }
+}
+
 /// Generate the entire Merkle tree, computing the authentication path for
 /// leaf_idx, and the resulting root node using Merkle's TreeHash algorithm.
 /// Expects the layer and tree parts of the tree_addr to be set, as well as the
@@ -17,22 +36,21 @@ use crate::wotsx1::{ LeafInfoX1, wots_gen_leafx1 };
 /// Applies the offset idx_offset to indices before building addresses, so that
 /// it is possible to continue counting indices across trees.
 /// This works by using the standard Merkle tree building algorithm.
-/// T: tree_height
-/// S: stack.len()
-pub fn wots_treehashx1<const T: usize, const S: usize>(
+pub fn wots_treehashx1<P: ParameterSet>(
   root: &mut[u8], auth_path: &mut[u8], ctx: &SpxCtx, leaf_idx: u32, 
   idx_offset: u32, tree_addr: &mut[u32], info: &mut LeafInfoX1
 ) 
 {
+  let tree_height = P::TREE_HEIGHT as u32;
   let mut idx =  0u32;
-  let max_idx = (1 << T) - 1;
-  let mut stack = [0u8; S];
+  let max_idx = (1 << tree_height) - 1;
+  let mut stack = [0u8; SPX_MAX_TREE_HEIGHT * SPX_MAX_N];
   loop {
     // Current logical node is at index[SPX_N].
     // We do this to minimize the number of copies needed during a thash
-    let mut current = [0u8; 2*SPX_N];    
+    let mut current = [0u8; 2*SPX_MAX_N];    
 
-    wots_gen_leafx1( &mut current[SPX_N..], ctx, idx + idx_offset, info);
+    wots_gen_leafx1::<P>( &mut current[P::N..], ctx, idx + idx_offset, info);
 
     // Now combine the freshly generated right node with previously
     //  generated left ones
@@ -44,16 +62,18 @@ pub fn wots_treehashx1<const T: usize, const S: usize>(
     loop {
 
       // Check if we hit the top of the tree
-      if h == T as u32 {
+      if h == tree_height {
         // We hit the root; return it
-        root[..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+        root[..P::N].copy_from_slice(&current[P::N..P::N*2]);
         return;
       }
       // Check if the node we have is a part of the
       //authentication path; if it is, write it out
-      let start = h as usize * SPX_N;
+      let start = h as usize * P::N;
       if (internal_idx ^ internal_leaf) == 0x01 {
-        auth_path[start..][..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+        #[cfg(all(test, feature = "hardened"))]
+        fault::inject(&mut current[P::N..]);
+        auth_path[start..][..P::N].copy_from_slice(&current[P::N..P::N*2]);
       }
 
        // Check if we're at a left child; if so, stop going up the stack
@@ -68,12 +88,12 @@ pub fn wots_treehashx1<const T: usize, const S: usize>(
       // Now combine the left and right logical nodes together
       // Set the address of the node we're creating.
       internal_idx_offset >>= 1;
-      set_tree_height(tree_addr, h + 1);
-      set_tree_index(tree_addr, internal_idx/2 + internal_idx_offset );
+      set_tree_height::<P>(tree_addr, h + 1);
+      set_tree_index::<P>(tree_addr, internal_idx/2 + internal_idx_offset );
 
-      current[  ..SPX_N].copy_from_slice(&stack[start..][..SPX_N]);
-      let tmp_current = current.clone();
-      thash::<2>( &mut current[SPX_N..], Some(&tmp_current), ctx, tree_addr);
+      current[  ..P::N].copy_from_slice(&stack[start..][..P::N]);
+      let tmp_current = current;
+      thash::<P>(&mut current[P::N..2*P::N], Some(&tmp_current), 2, ctx, tree_addr);
       h += 1; 
       internal_idx >>= 1;
       internal_leaf >>= 1;
@@ -81,37 +101,38 @@ pub fn wots_treehashx1<const T: usize, const S: usize>(
 
     // We've hit a left child; save the current for when we get the
     // corresponding right right
-    let start = h as usize * SPX_N;
-    stack[start..][..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+    let start = h as usize * P::N;
+    stack[start..][..P::N].copy_from_slice(&current[P::N..P::N*2]);
     idx += 1
   }
 }
 
-pub fn fors_treehashx1<const T: usize, const S: usize>(
+pub fn fors_treehashx1<P: ParameterSet>(
   root: &mut[u8], auth_path: &mut[u8], ctx: &SpxCtx, leaf_idx: u32, 
   idx_offset: u32, tree_addr: &mut[u32; 8], info: &mut ForsGenLeafInfo
 ) 
 {
-  let mut stack = [0u8; S];
+  let tree_height = P::FORS_HEIGHT as u32;
+  let mut stack = [0u8; SPX_MAX_FORS_HEIGHT * SPX_MAX_N];
   let mut idx =  0u32;
-  let max_idx = (1 << T) - 1;
+  let max_idx = (1 << tree_height) - 1;
   loop {
-    let mut current = [0u8; 2*SPX_N];    
+    let mut current = [0u8; 2*SPX_MAX_N];    
 
-    fors_gen_leafx1(&mut current[SPX_N..], ctx, idx + idx_offset, info);
+    fors_gen_leafx1::<P>(&mut current[P::N..], ctx, idx + idx_offset, info);
 
     let mut internal_idx_offset = idx_offset; //TODO: Refactor 
     let mut internal_idx = idx;
     let mut internal_leaf = leaf_idx;
     let mut h =  0u32;      
     loop {
-      if h == T as u32 {
-        root[..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+      if h == tree_height {
+        root[..P::N].copy_from_slice(&current[P::N..P::N*2]);
         return;
       }
-      let start = h as usize * SPX_N;
+      let start = h as usize * P::N;
       if (internal_idx ^ internal_leaf) == 0x01 {
-        auth_path[start..][..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+        auth_path[start..][..P::N].copy_from_slice(&current[P::N..P::N*2]);
       }
 
       if (internal_idx & 1) == 0 && idx < max_idx {
@@ -119,19 +140,19 @@ pub fn fors_treehashx1<const T: usize, const S: usize>(
       }
 
       internal_idx_offset >>= 1;
-      set_tree_height(tree_addr, h + 1);
-      set_tree_index(tree_addr, internal_idx/2 + internal_idx_offset );
+      set_tree_height::<P>(tree_addr, h + 1);
+      set_tree_index::<P>(tree_addr, internal_idx/2 + internal_idx_offset );
 
-      current[..SPX_N].copy_from_slice(&stack[start..][..SPX_N]);
-      let tmp_current = current.clone();
-      thash::<2>( &mut current[SPX_N..], Some(&tmp_current), ctx, tree_addr);
+      current[..P::N].copy_from_slice(&stack[start..][..P::N]);
+      let tmp_current = current;
+      thash::<P>(&mut current[P::N..2*P::N], Some(&tmp_current), 2, ctx, tree_addr);
       h += 1; 
       internal_idx >>= 1;
       internal_leaf >>= 1;
     }
 
-    let start = h as usize * SPX_N;
-    stack[start..][..SPX_N].copy_from_slice(&current[SPX_N..SPX_N*2]);
+    let start = h as usize * P::N;
+    stack[start..][..P::N].copy_from_slice(&current[P::N..P::N*2]);
     idx += 1
   }
 }
\ No newline at end of file
@@ -12,84 +12,82 @@ use crate::params::*;
 /// out and in have to be n-byte arrays.
 /// Interprets in as start-th value of the chain.
 /// addr has to contain the address of the chain.
-pub fn gen_chain(
+pub fn gen_chain<P: ParameterSet>(
   out: &mut[u8], input: &[u8], start: u32, 
   steps: u32, ctx: &SpxCtx, addr: &mut[u32]
 )
 {
-  out[..SPX_N].copy_from_slice(&input[..SPX_N]);
+  out[..P::N].copy_from_slice(&input[..P::N]);
 
   // Iterate 'steps' calls to the hash function.
   let mut i = start;
-  while i < (start+steps) && i < SPX_WOTS_W as u32 {
-    set_hash_addr(addr, i);
-    thash::<1>(out, None, ctx, addr);
+  while i < (start+steps) && i < P::WOTS_W as u32 {
+    set_hash_addr::<P>(addr, i);
+    thash::<P>(out, None, 1, ctx, addr);
     i += 1;
   }
 }
 
 /// base_w algorithm as described in draft.
 /// Interprets an array of bytes as integers in base w.
-/// This only works when log_w is a divisor of 8.
-pub fn base_w(output: &mut[u32], out_len: u32, input: &[u8])
+/// This only works when log_w is a divisor of 8, as for w = 4, 16 and 256.
+pub fn base_w<P: ParameterSet>(output: &mut[u32], out_len: u32, input: &[u8])
 {
   let mut idx = 0;
-  let mut out = 0;
   let mut total = 0u8;
   let mut bits = 0;
 
-  for _ in 0..out_len {
+  for out in output.iter_mut().take(out_len as usize) {
     if bits == 0 {
       total = input[idx];
       idx += 1;
       bits += 8;
     }
-    bits -= SPX_WOTS_LOGW;
-    output[out] = (((total >> bits) & (SPX_WOTS_W - 1) as u8)) as u32;
-    out += 1;
+    bits -= P::WOTS_LOGW;
+    *out = ((total >> bits) & (P::WOTS_W - 1) as u8) as u32;
   }
 }
 
 /// Computes the WOTS+ checksum over a message (in base_w).
-pub fn wots_checksum(csum_base_w: &mut[u32])
+pub fn wots_checksum<P: ParameterSet>(csum_base_w: &mut[u32])
 {
   let mut csum =  0u32;
-  let mut csum_bytes = [0u8; (SPX_WOTS_LEN2 * SPX_WOTS_LOGW + 7) / 8];
+  let mut csum_bytes = [0u8; 8];
 
   // Compute checksum.
-  for i in 0..SPX_WOTS_LEN1  {
-    csum += SPX_WOTS_W as u32 - 1 - csum_base_w[i] as u32;
+  for &digit in &csum_base_w[..P::WOTS_LEN1] {
+    csum += P::WOTS_W as u32 - 1 - digit;
   }
 
   // Convert checksum to base_w.
   // Make sure expected empty zero bits are the least significant bits.
-  csum = csum << ((8 - ((SPX_WOTS_LEN2 * SPX_WOTS_LOGW) % 8)) % 8);
-  let csum_sizeof = csum_bytes.len();
+  csum <<= (8 - ((P::WOTS_LEN2 * P::WOTS_LOGW) % 8)) % 8;
+  let csum_sizeof = (P::WOTS_LEN2 * P::WOTS_LOGW).div_ceil(8);
   ull_to_bytes(&mut csum_bytes, csum_sizeof, csum as u64);
-  base_w(
-    &mut csum_base_w[SPX_WOTS_LEN1..], SPX_WOTS_LEN2 as u32, &csum_bytes
+  base_w::<P>(
+    &mut csum_base_w[P::WOTS_LEN1..], P::WOTS_LEN2 as u32, &csum_bytes
   );
 }
 
 /// Takes a message and derives the matching chain lengths.
-pub fn chain_lengths(lengths: &mut[u32], msg: &[u8])
+pub fn chain_lengths<P: ParameterSet>(lengths: &mut[u32], msg: &[u8])
 {
-  base_w(lengths, SPX_WOTS_LEN1 as u32, msg);
-  wots_checksum(lengths);
+  base_w::<P>(lengths, P::WOTS_LEN1 as u32, msg);
+  wots_checksum::<P>(lengths);
 }
 
 /// Takes a WOTS signature and an n-byte message, computes a WOTS public key.
 /// Writes the computed public key to 'pk'.
-pub fn wots_pk_from_sig(
+pub fn wots_pk_from_sig<P: ParameterSet>(
   pk: &mut[u8], sig: &[u8], msg: &[u8], ctx: &SpxCtx, addr: &mut[u32]
 )
 {
-  let mut lengths = [0u32;  SPX_WOTS_LEN];
-  chain_lengths(&mut lengths, msg);
+  let mut lengths = [0u32; SPX_MAX_WOTS_LEN];
+  chain_lengths::<P>(&mut lengths, msg);
 
-  for i in 0..SPX_WOTS_LEN  {
-    set_chain_addr(addr, i as u32);
-    let steps = SPX_WOTS_W as u32 - 1 - lengths[i];
-    gen_chain(&mut pk[i*SPX_N..], &sig[i*SPX_N..], lengths[i], steps, ctx, addr);
+  for i in 0..P::WOTS_LEN  {
+    set_chain_addr::<P>(addr, i as u32);
+    let steps = P::WOTS_W as u32 - 1 - lengths[i];
+    gen_chain::<P>(&mut pk[i*P::N..], &sig[i*P::N..], lengths[i], steps, ctx, addr);
   }
 }